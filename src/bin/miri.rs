@@ -6,6 +6,7 @@ extern crate rustc_hir;
 extern crate rustc_interface;
 extern crate rustc_session;
 
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::env;
 use std::str::FromStr;
@@ -130,9 +131,20 @@ fn main() {
     let mut stacked_borrows = true;
     let mut communicate = false;
     let mut ignore_leaks = false;
+    let mut ignore_asm = false;
     let mut seed: Option<u64> = None;
     let mut tracked_pointer_tag: Option<miri::PtrId> = None;
-    let mut tracked_alloc_id: Option<miri::AllocId> = None;
+    let mut tracked_alloc_ids: BTreeSet<miri::AllocId> = BTreeSet::new();
+    let mut fake_pid: u32 = miri::MiriConfig::default().fake_pid;
+    let mut kernel_release = miri::MiriConfig::default().kernel_release;
+    let mut num_cpus = miri::MiriConfig::default().num_cpus;
+    let mut eintr_rate = 0.0;
+    let mut heap_profile = false;
+    let mut heap_limit: Option<u64> = None;
+    let mut oom_probability = 0.0;
+    let mut pipe_capacity = miri::MiriConfig::default().pipe_capacity;
+    let mut output_format = miri::OutputFormat::Text;
+    let mut cxchg_weak_failure_rate = 0.0;
     let mut rustc_args = vec![];
     let mut miri_args = vec![];
     let mut after_dashdash = false;
@@ -158,6 +170,12 @@ fn main() {
                 "-Zmiri-ignore-leaks" => {
                     ignore_leaks = true;
                 }
+                "-Zmiri-ignore-asm" => {
+                    ignore_asm = true;
+                }
+                "-Zmiri-heap-profile" => {
+                    heap_profile = true;
+                }
                 "--" => {
                     after_dashdash = true;
                 }
@@ -205,15 +223,109 @@ fn main() {
                     }
                 }
                 arg if arg.starts_with("-Zmiri-track-alloc-id=") => {
-                    let id: u64 = match arg.trim_start_matches("-Zmiri-track-alloc-id=").parse()
-                    {
-                        Ok(id) => id,
+                    // The flag can be passed several times, and takes a comma-separated list of ids.
+                    for id in arg.trim_start_matches("-Zmiri-track-alloc-id=").split(',') {
+                        let id: u64 = match id.parse() {
+                            Ok(id) => id,
+                            Err(err) => panic!(
+                                "-Zmiri-track-alloc-id requires a comma-separated list of valid `u64`s as the argument: {}",
+                                err
+                            ),
+                        };
+                        tracked_alloc_ids.insert(miri::AllocId(id));
+                    }
+                }
+                arg if arg.starts_with("-Zmiri-fake-pid=") => {
+                    let pid: u32 = match arg.trim_start_matches("-Zmiri-fake-pid=").parse() {
+                        Ok(pid) => pid,
+                        Err(err) => panic!(
+                            "-Zmiri-fake-pid requires a valid `u32` as the argument: {}",
+                            err
+                        ),
+                    };
+                    if pid == 0 || pid > i32::MAX as u32 {
+                        panic!("-Zmiri-fake-pid must be a positive `pid_t`");
+                    }
+                    fake_pid = pid;
+                }
+                arg if arg.starts_with("-Zmiri-output-format=") => {
+                    output_format = match arg.trim_start_matches("-Zmiri-output-format=") {
+                        "text" => miri::OutputFormat::Text,
+                        "json" => miri::OutputFormat::Json,
+                        _ => panic!("-Zmiri-output-format must be `text` or `json`"),
+                    };
+                }
+                arg if arg.starts_with("-Zmiri-num-cpus=") => {
+                    num_cpus = match arg.trim_start_matches("-Zmiri-num-cpus=").parse() {
+                        Ok(num_cpus) if num_cpus > 0 => num_cpus,
+                        _ => panic!("-Zmiri-num-cpus requires a positive `u32` as the argument"),
+                    };
+                }
+                arg if arg.starts_with("-Zmiri-fake-kernel-release=") => {
+                    let release = arg.trim_start_matches("-Zmiri-fake-kernel-release=");
+                    // `struct utsname` fields are 65 bytes long, including the null terminator.
+                    if release.is_empty() || release.len() > 64 {
+                        panic!("-Zmiri-fake-kernel-release must be between 1 and 64 bytes long");
+                    }
+                    kernel_release = release.to_owned();
+                }
+                arg if arg.starts_with("-Zmiri-eintr-rate=") => {
+                    let rate: f64 = match arg.trim_start_matches("-Zmiri-eintr-rate=").parse() {
+                        Ok(rate) => rate,
+                        Err(err) => panic!(
+                            "-Zmiri-eintr-rate requires a valid `f64` as the argument: {}",
+                            err
+                        ),
+                    };
+                    if !(0.0..=1.0).contains(&rate) {
+                        panic!("-Zmiri-eintr-rate must be between 0 and 1");
+                    }
+                    eintr_rate = rate;
+                }
+                arg if arg.starts_with("-Zmiri-cxchg-weak-failure-rate=") => {
+                    let rate: f64 = match arg.trim_start_matches("-Zmiri-cxchg-weak-failure-rate=").parse() {
+                        Ok(rate) => rate,
                         Err(err) => panic!(
-                            "-Zmiri-track-alloc-id requires a valid `u64` as the argument: {}",
+                            "-Zmiri-cxchg-weak-failure-rate requires a valid `f64` as the argument: {}",
                             err
                         ),
                     };
-                    tracked_alloc_id = Some(miri::AllocId(id));
+                    if !(0.0..=1.0).contains(&rate) {
+                        panic!("-Zmiri-cxchg-weak-failure-rate must be between 0 and 1");
+                    }
+                    cxchg_weak_failure_rate = rate;
+                }
+                arg if arg.starts_with("-Zmiri-heap-limit=") => {
+                    let limit: u64 = match arg.trim_start_matches("-Zmiri-heap-limit=").parse() {
+                        Ok(limit) => limit,
+                        Err(err) => panic!(
+                            "-Zmiri-heap-limit requires a valid `u64` as the argument: {}",
+                            err
+                        ),
+                    };
+                    heap_limit = Some(limit);
+                }
+                arg if arg.starts_with("-Zmiri-pipe-capacity=") => {
+                    pipe_capacity = match arg.trim_start_matches("-Zmiri-pipe-capacity=").parse() {
+                        Ok(capacity) => capacity,
+                        Err(err) => panic!(
+                            "-Zmiri-pipe-capacity requires a valid `u64` as the argument: {}",
+                            err
+                        ),
+                    };
+                }
+                arg if arg.starts_with("-Zmiri-oom-probability=") => {
+                    let probability: f64 = match arg.trim_start_matches("-Zmiri-oom-probability=").parse() {
+                        Ok(probability) => probability,
+                        Err(err) => panic!(
+                            "-Zmiri-oom-probability requires a valid `f64` as the argument: {}",
+                            err
+                        ),
+                    };
+                    if !(0.0..=1.0).contains(&probability) {
+                        panic!("-Zmiri-oom-probability must be between 0 and 1");
+                    }
+                    oom_probability = probability;
                 }
                 _ => {
                     rustc_args.push(arg);
@@ -245,11 +357,22 @@ fn main() {
         stacked_borrows,
         communicate,
         ignore_leaks,
+        ignore_asm,
         excluded_env_vars,
         seed,
         args: miri_args,
         tracked_pointer_tag,
-        tracked_alloc_id,
+        tracked_alloc_ids,
+        fake_pid,
+        kernel_release,
+        num_cpus,
+        eintr_rate,
+        cxchg_weak_failure_rate,
+        heap_profile,
+        heap_limit,
+        oom_probability,
+        pipe_capacity,
+        output_format,
     };
     rustc_driver::install_ice_hook();
     let result = rustc_driver::catch_fatal_errors(move || {
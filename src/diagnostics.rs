@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::fmt;
+use std::fmt::{self, Write};
 
 use log::trace;
 
@@ -12,7 +12,9 @@ pub enum TerminationInfo {
     Exit(i64),
     Abort(Option<String>),
     UnsupportedInIsolation(String),
-    ExperimentalUb { msg: String, url: String }
+    Deadlock,
+    ExperimentalUb { msg: String, url: String },
+    InlineAsm,
 }
 
 impl fmt::Debug for TerminationInfo {
@@ -27,18 +29,36 @@ impl fmt::Debug for TerminationInfo {
                 write!(f, "the evaluated program aborted execution: {}", msg),
             UnsupportedInIsolation(msg) =>
                 write!(f, "{}", msg),
+            Deadlock =>
+                write!(f, "the evaluated program deadlocked"),
             ExperimentalUb { msg, .. } =>
                 write!(f, "{}", msg),
+            InlineAsm =>
+                write!(f, "inline assembly is not supported"),
         }
     }
 }
 
 impl MachineStopType for TerminationInfo {}
 
+/// How Miri reports errors and other diagnostics.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Regular compiler diagnostics.
+    Text,
+    /// One JSON object per diagnostic and line on stderr, for consumption by other tools.
+    Json,
+}
+
 /// Miri specific diagnostics
 pub enum NonHaltingDiagnostic {
     PoppedTrackedPointerTag(Item),
     CreatedAlloc(AllocId),
+    ReadAlloc(AllocId),
+    WrittenAlloc(AllocId),
+    FreedAlloc(AllocId),
+    IoUringUnsupported,
+    UnsupportedPrctlOption(i32),
 }
 
 /// Emit a custom diagnostic without going through the miri-engine machinery
@@ -48,7 +68,7 @@ pub fn report_error<'tcx, 'mir>(
 ) -> Option<i64> {
     use InterpError::*;
 
-    let (title, helps) = match e.kind {
+    let (title, helps, alloc_id) = match e.kind {
         MachineStop(ref info) => {
             let info = info.downcast_ref::<TerminationInfo>().expect("invalid MachineStop payload");
             use TerminationInfo::*;
@@ -56,14 +76,22 @@ pub fn report_error<'tcx, 'mir>(
                 Exit(code) => return Some(*code),
                 Abort(_) =>
                     "abnormal termination",
-                UnsupportedInIsolation(_) =>
+                UnsupportedInIsolation(_) | InlineAsm =>
                     "unsupported operation",
+                Deadlock =>
+                    "deadlock",
                 ExperimentalUb { .. } =>
                     "Undefined Behavior",
             };
             let helps = match info {
                 UnsupportedInIsolation(_) =>
                     vec![format!("pass the flag `-Zmiri-disable-isolation` to disable isolation")],
+                InlineAsm =>
+                    vec![
+                        format!("the inline assembly is in `{}`", ecx.frame().instance),
+                        format!("consider using a pure-Rust implementation when `cfg(miri)` is set, or moving the assembly into a foreign function that Miri has a shim for"),
+                        format!("pass the flag `-Zmiri-ignore-asm` to skip assembly without outputs"),
+                    ],
                 ExperimentalUb { url, .. } =>
                     vec![
                         format!("this indicates a potential bug in the program: it performed an invalid operation, but the rules it violated are still experimental"),
@@ -71,7 +99,7 @@ pub fn report_error<'tcx, 'mir>(
                     ],
                 _ => vec![],
             };
-            (title, helps)
+            (title, helps, None)
         }
         _ => {
             let title = match e.kind {
@@ -84,7 +112,7 @@ pub fn report_error<'tcx, 'mir>(
                 _ =>
                     bug!("This error should be impossible in Miri: {}", e),
             };
-            let helps = match e.kind {
+            let mut helps = match e.kind {
                 Unsupported(UnsupportedOpInfo::NoMirFor(..)) =>
                     vec![format!("make sure to use a Miri sysroot, which you can prepare with `cargo miri setup`")],
                 Unsupported(_) =>
@@ -96,13 +124,169 @@ pub fn report_error<'tcx, 'mir>(
                     ],
                 _ => vec![],
             };
-            (title, helps)
+            // Point out how to find out where the allocation involved in the error comes from.
+            let alloc_id = match e.kind {
+                UndefinedBehavior(UndefinedBehaviorInfo::PointerUseAfterFree(alloc_id)) => Some(alloc_id),
+                UndefinedBehavior(UndefinedBehaviorInfo::PointerOutOfBounds { ptr, .. }) => Some(ptr.alloc_id),
+                _ => None,
+            };
+            if let Some(AllocId(id)) = alloc_id {
+                helps.push(format!("pass the flag `-Zmiri-track-alloc-id={}` to see where this allocation was created", id));
+            }
+            (title, helps, alloc_id)
         }
     };
 
     e.print_backtrace();
     let msg = e.to_string();
-    report_msg(ecx, &format!("{}: {}", title, msg), msg, &helps, true)
+    ecx.machine.output_format.formatter().report(ecx, title, msg, &helps, true, alloc_id, None)
+}
+
+/// Emits errors and notes in one of the formats selected by `-Zmiri-output-format`.
+trait DiagnosticFormatter {
+    /// Reports an error or note (depending on the `error` argument) of the given `kind` at the
+    /// current frame's current statement. `alloc_id` and `tags` are the allocation and the
+    /// Stacked Borrows tags that the diagnostic is about, if they are known. For errors, returns
+    /// the exit code to use, or `None` to let the reported error determine it.
+    fn report<'tcx, 'mir>(
+        &self,
+        ecx: &InterpCx<'mir, 'tcx, Evaluator<'tcx>>,
+        kind: &str,
+        msg: String,
+        helps: &[String],
+        error: bool,
+        alloc_id: Option<AllocId>,
+        tags: Option<&[PtrId]>,
+    ) -> Option<i64>;
+}
+
+/// Regular compiler diagnostics.
+struct TextFormatter;
+
+impl DiagnosticFormatter for TextFormatter {
+    fn report<'tcx, 'mir>(
+        &self,
+        ecx: &InterpCx<'mir, 'tcx, Evaluator<'tcx>>,
+        kind: &str,
+        msg: String,
+        helps: &[String],
+        error: bool,
+        _alloc_id: Option<AllocId>,
+        _tags: Option<&[PtrId]>,
+    ) -> Option<i64> {
+        if error {
+            report_msg(ecx, &format!("{}: {}", kind, msg), msg, helps, true)
+        } else {
+            report_msg(ecx, kind, msg, helps, false)
+        }
+    }
+}
+
+/// One JSON object per diagnostic, see `report_json`.
+struct JsonFormatter;
+
+impl DiagnosticFormatter for JsonFormatter {
+    fn report<'tcx, 'mir>(
+        &self,
+        ecx: &InterpCx<'mir, 'tcx, Evaluator<'tcx>>,
+        kind: &str,
+        msg: String,
+        helps: &[String],
+        error: bool,
+        alloc_id: Option<AllocId>,
+        tags: Option<&[PtrId]>,
+    ) -> Option<i64> {
+        report_json(ecx, kind, &msg, helps, error, alloc_id, tags);
+        // Like the compiler, we exit with 1 after reporting an error.
+        Some(1)
+    }
+}
+
+impl OutputFormat {
+    fn formatter(self) -> &'static dyn DiagnosticFormatter {
+        match self {
+            OutputFormat::Text => &TextFormatter,
+            OutputFormat::Json => &JsonFormatter,
+        }
+    }
+}
+
+/// Report an error or note as a single line of JSON on stderr. The object has the following fields:
+/// `kind` (the category of the diagnostic), `level` (`"error"` or `"note"`), `message`, `helps`,
+/// `thread_id` and `thread_name`, `frames` (the stacktrace, innermost frame first, each with
+/// `function`, `file`, `line` and `column`), `alloc_id` (the allocation the diagnostic is about)
+/// and `tags` (the Stacked Borrows tags it is about). The last two are `null` when unknown.
+fn report_json<'tcx, 'mir>(
+    ecx: &InterpCx<'mir, 'tcx, Evaluator<'tcx>>,
+    kind: &str,
+    msg: &str,
+    helps: &[String],
+    error: bool,
+    alloc_id: Option<AllocId>,
+    tags: Option<&[PtrId]>,
+) {
+    let source_map = ecx.tcx.sess.source_map();
+    let frames: Vec<String> = ecx
+        .generate_stacktrace(None)
+        .iter()
+        .map(|frame_info| {
+            let loc = source_map.lookup_char_pos(frame_info.call_site.lo());
+            json_object(&[
+                ("function", json_string(&frame_info.instance.to_string())),
+                ("file", json_string(&loc.file.name.to_string())),
+                ("line", loc.line.to_string()),
+                ("column", (loc.col.0 + 1).to_string()),
+            ])
+        })
+        .collect();
+    // Miri is single-threaded, so every diagnostic comes from the main thread.
+    let (thread_id, thread_name) = (0, "main");
+    let object = json_object(&[
+        ("kind", json_string(kind)),
+        ("level", json_string(if error { "error" } else { "note" })),
+        ("message", json_string(msg)),
+        ("helps", json_array(helps.iter().map(|help| json_string(help)).collect())),
+        ("thread_id", thread_id.to_string()),
+        ("thread_name", json_string(thread_name)),
+        ("frames", json_array(frames)),
+        ("alloc_id", alloc_id.map_or_else(|| "null".to_owned(), |AllocId(id)| id.to_string())),
+        ("tags", tags.map_or_else(
+            || "null".to_owned(),
+            |tags| json_array(tags.iter().map(|tag| tag.to_string()).collect()),
+        )),
+    ]);
+    eprintln!("{}", object);
+}
+
+/// Builds a JSON object from its fields, whose values must already be JSON.
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> =
+        fields.iter().map(|(name, value)| format!("{}:{}", json_string(name), value)).collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Builds a JSON array from its elements, which must already be JSON.
+fn json_array(elements: Vec<String>) -> String {
+    format!("[{}]", elements.join(","))
+}
+
+/// Quotes and escapes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
 }
 
 /// Report an error or note (depending on the `error` argument) at the current frame's current statement.
@@ -175,13 +359,30 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         DIAGNOSTICS.with(|diagnostics| {
             for e in diagnostics.borrow_mut().drain(..) {
                 use NonHaltingDiagnostic::*;
-                let msg = match e {
+                let tags = match e {
+                    PoppedTrackedPointerTag(item) => match item.tag() {
+                        Tag::Tagged(id) => Some(vec![id]),
+                        Tag::Untagged => None,
+                    },
+                    _ => None,
+                };
+                let (title, msg) = match e {
                     PoppedTrackedPointerTag(item) =>
-                        format!("popped tracked tag for item {:?}", item),
+                        ("tracking was triggered", format!("popped tracked tag for item {:?}", item)),
                     CreatedAlloc(AllocId(id)) =>
-                        format!("created allocation with id {}", id),
+                        ("tracking was triggered", format!("created allocation with id {}", id)),
+                    ReadAlloc(AllocId(id)) =>
+                        ("tracking was triggered", format!("read from allocation with id {}", id)),
+                    WrittenAlloc(AllocId(id)) =>
+                        ("tracking was triggered", format!("wrote to allocation with id {}", id)),
+                    FreedAlloc(AllocId(id)) =>
+                        ("tracking was triggered", format!("freed allocation with id {}", id)),
+                    IoUringUnsupported =>
+                        ("unsupported operation", format!("io_uring is not supported; consider using a non-io_uring async runtime for testing under Miri")),
+                    UnsupportedPrctlOption(option) =>
+                        ("unsupported operation", format!("`prctl` option {} is not supported, returning `EINVAL`", option)),
                 };
-                report_msg(this, "tracking was triggered", msg, &[], false);
+                this.machine.output_format.formatter().report(this, title, msg, &[], false, None, tags.as_deref());
             }
         });
     }
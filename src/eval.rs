@@ -1,5 +1,6 @@
 //! Main evaluator loop and setting up the initial stack frame.
 
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::convert::TryFrom;
 
@@ -23,6 +24,8 @@ pub struct MiriConfig {
     pub communicate: bool,
     /// Determines if memory leaks should be ignored.
     pub ignore_leaks: bool,
+    /// Determines if inline assembly should be skipped instead of reported.
+    pub ignore_asm: bool,
     /// Environment variables that should always be isolated from the host.
     pub excluded_env_vars: Vec<String>,
     /// Command-line arguments passed to the interpreted program.
@@ -31,8 +34,28 @@ pub struct MiriConfig {
     pub seed: Option<u64>,
     /// The stacked borrow id to report about
     pub tracked_pointer_tag: Option<PtrId>,
-    /// The allocation id to report about.
-    pub tracked_alloc_id: Option<AllocId>,
+    /// The allocation ids to report about.
+    pub tracked_alloc_ids: BTreeSet<AllocId>,
+    /// The process id reported to the interpreted program when isolation is enabled.
+    pub fake_pid: u32,
+    /// The kernel release reported by `uname`.
+    pub kernel_release: String,
+    /// The number of CPUs reported to the interpreted program.
+    pub num_cpus: u32,
+    /// The probability with which interruptible calls like `read` fail with `EINTR`.
+    pub eintr_rate: f64,
+    /// The probability with which `compare_exchange_weak` fails spuriously.
+    pub cxchg_weak_failure_rate: f64,
+    /// Determines if heap usage statistics should be reported at the end of the execution.
+    pub heap_profile: bool,
+    /// The number of live heap bytes beyond which allocations fail.
+    pub heap_limit: Option<u64>,
+    /// The probability with which heap allocations fail.
+    pub oom_probability: f64,
+    /// The number of bytes that a pipe can hold before writing to it blocks.
+    pub pipe_capacity: u64,
+    /// The format of error messages and other diagnostics.
+    pub output_format: OutputFormat,
 }
 
 impl Default for MiriConfig {
@@ -42,11 +65,22 @@ impl Default for MiriConfig {
             stacked_borrows: true,
             communicate: false,
             ignore_leaks: false,
+            ignore_asm: false,
             excluded_env_vars: vec![],
             args: vec![],
             seed: None,
             tracked_pointer_tag: None,
-            tracked_alloc_id: None,
+            tracked_alloc_ids: BTreeSet::new(),
+            fake_pid: 1000,
+            kernel_release: "5.15.0".to_owned(),
+            num_cpus: NUM_CPUS,
+            eintr_rate: 0.0,
+            cxchg_weak_failure_rate: 0.0,
+            heap_profile: false,
+            heap_limit: None,
+            oom_probability: 0.0,
+            pipe_capacity: 65536,
+            output_format: OutputFormat::Text,
         }
     }
 }
@@ -63,12 +97,12 @@ pub fn create_ecx<'mir, 'tcx: 'mir>(
     let mut ecx = InterpCx::new(
         tcx.at(rustc_span::source_map::DUMMY_SP),
         ty::ParamEnv::reveal_all(),
-        Evaluator::new(config.communicate, config.validate),
+        Evaluator::new(&config),
         MemoryExtra::new(
             StdRng::seed_from_u64(config.seed.unwrap_or(0)),
             config.stacked_borrows,
             config.tracked_pointer_tag,
-            config.tracked_alloc_id,
+            config.tracked_alloc_ids,
         ),
     );
     // Complete initialization.
@@ -198,20 +232,27 @@ pub fn eval_main<'tcx>(tcx: TyCtxt<'tcx>, main_id: DefId, config: MiriConfig) ->
     // Perform the main execution.
     let res: InterpResult<'_, i64> = (|| {
         // Main loop.
-        while ecx.step()? {
-            ecx.process_diagnostics();
-        }
+        while ecx.step_with_bookkeeping()? {}
         // Read the return code pointer *before* we run TLS destructors, to assert
         // that it was written to by the time that `start` lang item returned.
         let return_code = ecx.read_scalar(ret_place.into())?.not_undef()?.to_machine_isize(&ecx)?;
         // Global destructors.
         ecx.run_tls_dtors()?;
+        // Handlers registered with `atexit`.
+        ecx.run_atexit_handlers(/*quick_exit:*/ false)?;
         Ok(return_code)
     })();
 
     // Machine cleanup.
     EnvVars::cleanup(&mut ecx).unwrap();
 
+    if let Some(profile) = &ecx.machine.heap_profile {
+        eprintln!(
+            "heap profile: {} total allocations, {} peak live bytes",
+            profile.allocations, profile.peak_live_bytes
+        );
+    }
+
     // Process the result.
     match res {
         Ok(return_code) => {
@@ -12,7 +12,7 @@ use rustc_middle::ty::{
 use rustc_hir::def_id::{DefId, CRATE_DEF_INDEX};
 use rustc_span::source_map::DUMMY_SP;
 
-use rand::RngCore;
+use rand::{Rng, RngCore};
 
 use crate::*;
 
@@ -73,6 +73,20 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             .not_undef()
     }
 
+    /// Like `eval_libc`, but returns `None` instead of panicking if `libc` does not have the
+    /// constant (e.g. because it is too old).
+    fn try_eval_libc(&mut self, name: &str) -> InterpResult<'tcx, Option<Scalar<Tag>>> {
+        let this = self.eval_context_mut();
+        let did = match try_resolve_did(this.tcx.tcx, &["libc", name]) {
+            Some(did) => did,
+            None => return Ok(None),
+        };
+        let instance = ty::Instance::mono(this.tcx.tcx, did);
+        let cid = GlobalId { instance, promoted: None };
+        let const_val = this.const_eval_raw(cid)?;
+        Ok(Some(this.read_scalar(const_val.into())?.not_undef()?))
+    }
+
     /// Helper function to get a `windows` constant as a `Scalar`.
     fn eval_windows(&mut self, name: &str) -> InterpResult<'tcx, Scalar<Tag>> {
         self.eval_context_mut()
@@ -144,6 +158,40 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         this.memory.write_bytes(ptr, data.iter().copied())
     }
 
+    /// Emulates `getentropy`, which fills the buffer with random bytes like `gen_random`, but
+    /// refuses requests for more than 256 bytes. Returns 0 on success and -1 on failure.
+    fn getentropy(
+        &mut self,
+        ptr_op: OpTy<'tcx, Tag>,
+        len_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let ptr = this.read_scalar(ptr_op)?.not_undef()?;
+        let len = this.read_scalar(len_op)?.to_machine_usize(this)?;
+        if len > 256 {
+            let eio = this.eval_libc("EIO")?;
+            this.set_last_error(eio)?;
+            return Ok(-1);
+        }
+        this.gen_random(ptr, len)?;
+        Ok(0)
+    }
+
+    /// Decides whether an interruptible call should fail with `EINTR`, with the probability set by
+    /// `-Zmiri-eintr-rate`. If it should, this sets the last error and returns `true`; the caller
+    /// then has to return -1 without doing anything else.
+    fn inject_eintr(&mut self) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        let rate = this.machine.eintr_rate;
+        if rate > 0.0 && this.memory.extra.rng.get_mut().gen_bool(rate) {
+            let eintr = this.eval_libc("EINTR")?;
+            this.set_last_error(eintr)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     /// Call a function: Push the stack frame and pass the arguments.
     /// For now, arguments must be scalars (so that the caller does not have to know the layout).
     fn call_function(
@@ -383,6 +431,69 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         }
         Ok(())
     }
+
+    /// Executes one step of the program, like `step`, together with the bookkeeping that has to
+    /// happen for every step: inline assembly is handled, the step is counted, and diagnostics
+    /// are emitted. Every loop that runs program code must step through this.
+    fn step_with_bookkeeping(&mut self) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        this.handle_inline_asm()?;
+        if !this.step()? {
+            return Ok(false);
+        }
+        this.machine.steps += 1;
+        this.process_diagnostics();
+        Ok(true)
+    }
+
+    /// Called before every step: if the next thing to execute is inline assembly, which the
+    /// interpreter cannot run, either stop with `TerminationInfo::InlineAsm` or, with
+    /// `-Zmiri-ignore-asm`, skip it. Only assembly without outputs can be skipped, since there
+    /// would be nothing sensible to write to them.
+    fn handle_inline_asm(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let frame = match this.stack().last() {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        // While unwinding, there is no current location.
+        let loc = match frame.loc {
+            Some(loc) => loc,
+            None => return Ok(()),
+        };
+        let block = &frame.body.basic_blocks()[loc.block];
+        let (target, has_outputs) = match block.statements.get(loc.statement_index) {
+            Some(statement) => match &statement.kind {
+                mir::StatementKind::LlvmInlineAsm(asm) => (None, !asm.outputs.is_empty()),
+                _ => return Ok(()),
+            },
+            None => match &block.terminator().kind {
+                mir::TerminatorKind::InlineAsm { operands, destination, .. } => {
+                    let has_outputs = operands.iter().any(|operand| match operand {
+                        mir::InlineAsmOperand::Out { place, .. } => place.is_some(),
+                        mir::InlineAsmOperand::InOut { out_place, .. } => out_place.is_some(),
+                        _ => false,
+                    });
+                    (Some(*destination), has_outputs)
+                }
+                _ => return Ok(()),
+            },
+        };
+
+        if !this.machine.ignore_asm {
+            throw_machine_stop!(TerminationInfo::InlineAsm);
+        }
+        if has_outputs {
+            throw_unsup_format!("skipping inline assembly with outputs is not supported");
+        }
+        match target {
+            None => this.frame_mut().loc.as_mut().unwrap().statement_index += 1,
+            Some(Some(destination)) => this.go_to_block(destination),
+            Some(None) => throw_unsup_format!("skipping inline assembly that does not return is not supported"),
+        }
+        Ok(())
+    }
+
     /// Helper function used inside the shims of foreign functions to assert that the target OS
     /// is `target_os`. It panics showing a message with the `name` of the foreign function
     /// if this is not the case.
@@ -37,15 +37,20 @@ pub use crate::shims::env::{EnvVars, EvalContextExt as EnvEvalContextExt};
 pub use crate::shims::foreign_items::EvalContextExt as ForeignItemsEvalContextExt;
 pub use crate::shims::fs::{DirHandler, EvalContextExt as FileEvalContextExt, FileHandler};
 pub use crate::shims::intrinsics::EvalContextExt as IntrinsicsEvalContextExt;
+pub use crate::shims::io_uring::EvalContextExt as IoUringEvalContextExt;
+pub use crate::shims::mmap::{EvalContextExt as MmapEvalContextExt, Protection};
+pub use crate::shims::mqueue::{EvalContextExt as MessageQueueEvalContextExt, MessageQueueHandler};
 pub use crate::shims::os_str::EvalContextExt as OsStrEvalContextExt;
 pub use crate::shims::panic::{CatchUnwindData, EvalContextExt as PanicEvalContextExt};
+pub use crate::shims::process::{EvalContextExt as ProcessEvalContextExt, JmpTarget, MIN_JMP_TOKEN};
+pub use crate::shims::sync::{EvalContextExt as SyncEvalContextExt, OnceCompletion, WindowsMutexHandler};
 pub use crate::shims::time::EvalContextExt as TimeEvalContextExt;
 pub use crate::shims::tls::{EvalContextExt as TlsEvalContextExt, TlsData};
 pub use crate::shims::EvalContextExt as ShimsEvalContextExt;
 
 pub use crate::diagnostics::{
     register_diagnostic, report_error, EvalContextExt as DiagnosticsEvalContextExt,
-    TerminationInfo, NonHaltingDiagnostic,
+    TerminationInfo, NonHaltingDiagnostic, OutputFormat,
 };
 pub use crate::eval::{create_ecx, eval_main, MiriConfig};
 pub use crate::helpers::EvalContextExt as HelpersEvalContextExt;
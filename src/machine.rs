@@ -3,6 +3,7 @@
 
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::num::NonZeroU64;
 use std::rc::Rc;
 use std::time::Instant;
@@ -26,7 +27,7 @@ use crate::*;
 pub const PAGE_SIZE: u64 = 4 * 1024; // FIXME: adjust to target architecture
 pub const STACK_ADDR: u64 = 32 * PAGE_SIZE; // not really about the "stack", but where we start assigning integer addresses to allocations
 pub const STACK_SIZE: u64 = 16 * PAGE_SIZE; // whatever
-pub const NUM_CPUS: u64 = 1;
+pub const NUM_CPUS: u32 = 1; // the default for `-Zmiri-num-cpus`
 
 /// Extra data stored with each stack frame
 #[derive(Debug)]
@@ -38,6 +39,14 @@ pub struct FrameData<'tcx> {
     /// called by `try`). When this frame is popped during unwinding a panic,
     /// we stop unwinding, use the `CatchUnwindData` to handle catching.
     pub catch_unwind: Option<CatchUnwindData<'tcx>>,
+
+    /// If this is Some(), then this is the frame of the initialization function of a once object
+    /// (such as a `dispatch_once_t`). When this frame returns normally, we use the
+    /// `OnceCompletion` to mark the once object as done.
+    pub once_completion: Option<OnceCompletion<'tcx>>,
+
+    /// A unique id of this frame, used to tell whether a `jmp_buf` was initialized in it.
+    pub frame_id: u64,
 }
 
 /// Extra memory kinds
@@ -49,6 +58,10 @@ pub enum MiriMemoryKind {
     C,
     /// Windows `HeapAlloc` memory.
     WinHeap,
+    /// Anonymous `mmap` memory.
+    Mmap,
+    /// Objects created by the `dispatch_*_create` functions, such as `dispatch_semaphore_t`.
+    Dispatch,
     /// Memory for args, errno, extern statics and other parts of the machine-managed environment.
     /// This memory may leak.
     Machine,
@@ -71,6 +84,14 @@ impl Into<MemoryKind<MiriMemoryKind>> for MiriMemoryKind {
 pub struct AllocExtra {
     /// Stacked Borrows state is only added if it is enabled.
     pub stacked_borrows: Option<stacked_borrows::AllocExtra>,
+    /// The access permissions set by `mmap` and `mprotect`; only present for `mmap`ed memory.
+    /// `mprotect` does not get mutable access to the allocation, so we need a `RefCell` here.
+    pub mmap_protection: Option<RefCell<RangeMap<Protection>>>,
+    /// The size of the last atomic access to each byte, used to detect mixed-size atomic accesses.
+    /// Created on the first atomic access; non-atomic writes reset the affected bytes to `None`.
+    pub atomic_access_sizes: RefCell<Option<RangeMap<Option<Size>>>>,
+    /// Whether this allocation is tracked with `-Zmiri-track-alloc-id`.
+    pub tracked: bool,
 }
 
 /// Extra global memory data
@@ -86,13 +107,13 @@ pub struct MemoryExtra {
     /// Needs to be queried by ptr_to_int, hence needs interior mutability.
     pub(crate) rng: RefCell<StdRng>,
 
-    /// An allocation ID to report when it is being allocated
-    /// (helps for debugging memory leaks).
-    tracked_alloc_id: Option<AllocId>,
+    /// The allocation IDs to report when they are being allocated, accessed or deallocated
+    /// (helps for debugging memory leaks and use-after-free bugs).
+    tracked_alloc_ids: BTreeSet<AllocId>,
 }
 
 impl MemoryExtra {
-    pub fn new(rng: StdRng, stacked_borrows: bool, tracked_pointer_tag: Option<PtrId>, tracked_alloc_id: Option<AllocId>) -> Self {
+    pub fn new(rng: StdRng, stacked_borrows: bool, tracked_pointer_tag: Option<PtrId>, tracked_alloc_ids: BTreeSet<AllocId>) -> Self {
         let stacked_borrows = if stacked_borrows {
             Some(Rc::new(RefCell::new(stacked_borrows::GlobalState::new(tracked_pointer_tag))))
         } else {
@@ -103,7 +124,7 @@ impl MemoryExtra {
             intptrcast: Default::default(),
             extern_statics: FxHashMap::default(),
             rng: RefCell::new(rng),
-            tracked_alloc_id,
+            tracked_alloc_ids,
         }
     }
 
@@ -150,6 +171,29 @@ impl MemoryExtra {
     }
 }
 
+/// Heap usage statistics, collected with `-Zmiri-heap-profile`.
+#[derive(Debug, Default)]
+pub struct HeapProfile {
+    /// The total number of heap allocations.
+    pub allocations: u64,
+    /// The number of heap bytes that are currently allocated.
+    pub live_bytes: u64,
+    /// The largest value `live_bytes` ever had.
+    pub peak_live_bytes: u64,
+}
+
+impl HeapProfile {
+    pub(crate) fn allocated(&mut self, size: u64) {
+        self.allocations += 1;
+        self.live_bytes += size;
+        self.peak_live_bytes = self.peak_live_bytes.max(self.live_bytes);
+    }
+
+    pub(crate) fn deallocated(&mut self, size: u64) {
+        self.live_bytes -= size;
+    }
+}
+
 /// The machine itself.
 pub struct Evaluator<'tcx> {
     /// Environment variables set by `setenv`.
@@ -176,8 +220,13 @@ pub struct Evaluator<'tcx> {
     /// Whether to enforce the validity invariant.
     pub(crate) validate: bool,
 
+    /// Whether to skip inline assembly instead of reporting it.
+    pub(crate) ignore_asm: bool,
+
     pub(crate) file_handler: FileHandler,
     pub(crate) dir_handler: DirHandler,
+    pub(crate) message_queues: MessageQueueHandler,
+    pub(crate) windows_mutexes: WindowsMutexHandler,
 
     /// The temporary used for storing the argument of
     /// the call to `miri_start_panic` (the panic payload) when unwinding.
@@ -186,10 +235,76 @@ pub struct Evaluator<'tcx> {
 
     /// The "time anchor" for this machine's monotone clock (for `Instant` simulation).
     pub(crate) time_anchor: Instant,
+
+    /// The process id that `getpid` returns when isolation is enabled.
+    pub(crate) fake_pid: u32,
+
+    /// The "dumpable" attribute of the process, as set by `prctl(PR_SET_DUMPABLE)`.
+    pub(crate) dumpable: bool,
+
+    /// The kernel release that `uname` reports.
+    pub(crate) kernel_release: String,
+
+    /// The strings that `strerror` returned so far, by error number. Like the static buffers
+    /// they live in on real systems, they are allocated once and never freed.
+    pub(crate) strerror_messages: FxHashMap<i32, Pointer<Tag>>,
+
+    /// The number of CPUs that `sysconf` and `GetSystemInfo` report.
+    pub(crate) num_cpus: u32,
+
+    /// The probability with which interruptible calls fail with `EINTR`.
+    pub(crate) eintr_rate: f64,
+
+    /// The probability with which a weak compare-exchange fails even though the comparison succeeded.
+    pub(crate) cxchg_weak_failure_rate: f64,
+
+    /// Heap usage statistics, if `-Zmiri-heap-profile` is enabled.
+    pub(crate) heap_profile: Option<HeapProfile>,
+
+    /// The number of heap bytes that are currently allocated.
+    pub(crate) heap_live_bytes: u64,
+
+    /// The size of each live heap allocation that is counted in `heap_live_bytes`.
+    pub(crate) heap_allocations: FxHashMap<AllocId, u64>,
+
+    /// Heap allocations fail when they would make `heap_live_bytes` exceed this limit.
+    pub(crate) heap_limit: Option<u64>,
+
+    /// The probability with which a heap allocation fails.
+    pub(crate) oom_probability: f64,
+
+    /// The number of bytes that a pipe can hold before writing to it blocks.
+    pub(crate) pipe_capacity: u64,
+
+    /// The format of error messages and other diagnostics.
+    pub(crate) output_format: OutputFormat,
+
+    /// Handlers registered with `atexit` and `__cxa_atexit`, together with their argument (if any).
+    pub(crate) atexit_handlers: Vec<(ty::Instance<'tcx>, Option<Scalar<Tag>>)>,
+
+    /// Handlers registered with `at_quick_exit`, which only `quick_exit` runs.
+    pub(crate) quick_exit_handlers: Vec<ty::Instance<'tcx>>,
+
+    /// The targets recorded by `setjmp` in frames that are still live, indexed by the id of the
+    /// frame that called `setjmp`.
+    pub(crate) jmp_targets: FxHashMap<u64, Vec<JmpTarget<'tcx>>>,
+
+    /// The frame id and the index into the targets of that frame for every token that a live
+    /// `jmp_buf` can hold.
+    pub(crate) jmp_tokens: FxHashMap<u64, (u64, usize)>,
+
+    /// The token to hand out for the next `setjmp` call.
+    pub(crate) next_jmp_token: u64,
+
+    /// The id to assign to the next stack frame.
+    pub(crate) next_frame_id: u64,
+
+    /// The number of interpreter steps executed so far, as reported by `miri_step_count`.
+    pub(crate) steps: u64,
 }
 
 impl<'tcx> Evaluator<'tcx> {
-    pub(crate) fn new(communicate: bool, validate: bool) -> Self {
+    pub(crate) fn new(config: &MiriConfig) -> Self {
         Evaluator {
             // `env_vars` could be initialized properly here if `Memory` were available before
             // calling this method.
@@ -199,12 +314,36 @@ impl<'tcx> Evaluator<'tcx> {
             cmd_line: None,
             last_error: None,
             tls: TlsData::default(),
-            communicate,
-            validate,
+            communicate: config.communicate,
+            validate: config.validate,
+            ignore_asm: config.ignore_asm,
             file_handler: Default::default(),
             dir_handler: Default::default(),
+            message_queues: Default::default(),
+            windows_mutexes: Default::default(),
             panic_payload: None,
             time_anchor: Instant::now(),
+            fake_pid: config.fake_pid,
+            dumpable: true,
+            kernel_release: config.kernel_release.clone(),
+            strerror_messages: FxHashMap::default(),
+            num_cpus: config.num_cpus,
+            eintr_rate: config.eintr_rate,
+            cxchg_weak_failure_rate: config.cxchg_weak_failure_rate,
+            heap_profile: if config.heap_profile { Some(HeapProfile::default()) } else { None },
+            heap_live_bytes: 0,
+            heap_allocations: FxHashMap::default(),
+            heap_limit: config.heap_limit,
+            oom_probability: config.oom_probability,
+            pipe_capacity: config.pipe_capacity,
+            output_format: config.output_format,
+            atexit_handlers: Vec::new(),
+            quick_exit_handlers: Vec::new(),
+            jmp_targets: FxHashMap::default(),
+            jmp_tokens: FxHashMap::default(),
+            next_jmp_token: MIN_JMP_TOKEN,
+            next_frame_id: 0,
+            steps: 0,
         }
     }
 }
@@ -368,12 +507,18 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
         alloc: Cow<'b, Allocation>,
         kind: Option<MemoryKind<Self::MemoryKind>>,
     ) -> (Cow<'b, Allocation<Self::PointerTag, Self::AllocExtra>>, Self::PointerTag) {
-        if Some(id) == memory_extra.tracked_alloc_id {
+        let tracked = memory_extra.tracked_alloc_ids.contains(&id);
+        if tracked {
             register_diagnostic(NonHaltingDiagnostic::CreatedAlloc(id));
         }
 
         let kind = kind.expect("we set our STATIC_KIND so this cannot be None");
         let alloc = alloc.into_owned();
+        let mmap_protection = if kind == MiriMemoryKind::Mmap.into() {
+            Some(RefCell::new(RangeMap::new(alloc.size, Protection::READ_WRITE)))
+        } else {
+            None
+        };
         let (stacks, base_tag) =
             if let Some(stacked_borrows) = memory_extra.stacked_borrows.as_ref() {
                 let (stacks, base_tag) =
@@ -394,7 +539,12 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
                     Tag::Untagged
                 }
             },
-            AllocExtra { stacked_borrows: stacks },
+            AllocExtra {
+                stacked_borrows: stacks,
+                mmap_protection,
+                atomic_access_sizes: RefCell::new(None),
+                tracked,
+            },
         );
         (Cow::Owned(alloc), base_tag)
     }
@@ -428,7 +578,9 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
         let call_id = stacked_borrows.map_or(NonZeroU64::new(1).unwrap(), |stacked_borrows| {
             stacked_borrows.borrow_mut().new_call()
         });
-        Ok(FrameData { call_id, catch_unwind: None })
+        let frame_id = ecx.machine.next_frame_id;
+        ecx.machine.next_frame_id += 1;
+        Ok(FrameData { call_id, catch_unwind: None, once_completion: None, frame_id })
     }
 
     #[inline(always)]
@@ -464,6 +616,14 @@ impl AllocationExtra<Tag> for AllocExtra {
         ptr: Pointer<Tag>,
         size: Size,
     ) -> InterpResult<'tcx> {
+        if alloc.extra.tracked {
+            register_diagnostic(NonHaltingDiagnostic::ReadAlloc(ptr.alloc_id));
+        }
+        if let Some(ref mmap_protection) = alloc.extra.mmap_protection {
+            if mmap_protection.borrow().iter(ptr.offset, size).any(|prot| !prot.readable) {
+                throw_ub_format!("access to PROT_NONE memory");
+            }
+        }
         if let Some(ref stacked_borrows) = alloc.extra.stacked_borrows {
             stacked_borrows.memory_read(ptr, size)
         } else {
@@ -477,6 +637,24 @@ impl AllocationExtra<Tag> for AllocExtra {
         ptr: Pointer<Tag>,
         size: Size,
     ) -> InterpResult<'tcx> {
+        if alloc.extra.tracked {
+            register_diagnostic(NonHaltingDiagnostic::WrittenAlloc(ptr.alloc_id));
+        }
+        if let Some(ref mmap_protection) = alloc.extra.mmap_protection {
+            for prot in mmap_protection.borrow().iter(ptr.offset, size) {
+                if !prot.readable {
+                    throw_ub_format!("access to PROT_NONE memory");
+                }
+                if !prot.writable {
+                    throw_ub_format!("write access to memory that is not `PROT_WRITE`");
+                }
+            }
+        }
+        if let Some(ref mut atomic_access_sizes) = *alloc.extra.atomic_access_sizes.get_mut() {
+            for access_size in atomic_access_sizes.iter_mut(ptr.offset, size) {
+                *access_size = None;
+            }
+        }
         if let Some(ref mut stacked_borrows) = alloc.extra.stacked_borrows {
             stacked_borrows.memory_written(ptr, size)
         } else {
@@ -490,6 +668,9 @@ impl AllocationExtra<Tag> for AllocExtra {
         ptr: Pointer<Tag>,
         size: Size,
     ) -> InterpResult<'tcx> {
+        if alloc.extra.tracked {
+            register_diagnostic(NonHaltingDiagnostic::FreedAlloc(ptr.alloc_id));
+        }
         if let Some(ref mut stacked_borrows) = alloc.extra.stacked_borrows {
             stacked_borrows.memory_deallocated(ptr, size)
         } else {
@@ -503,7 +684,7 @@ impl MayLeak for MiriMemoryKind {
     fn may_leak(self) -> bool {
         use self::MiriMemoryKind::*;
         match self {
-            Rust | C | WinHeap | Env => false,
+            Rust | C | WinHeap | Mmap | Dispatch | Env => false,
             Machine | Global => true,
         }
     }
@@ -217,6 +217,45 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         }
     }
 
+    fn putenv(&mut self, string_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
+        let mut this = self.eval_context_mut();
+        let target_os = &this.tcx.sess.target.target.target_os;
+        assert!(target_os == "linux" || target_os == "macos", "`putenv` is only available for the UNIX target family");
+
+        let string_ptr = this.read_scalar(string_op)?.not_undef()?;
+        // FIXME: POSIX says that the string itself becomes part of the environment, so that
+        // modifying it later changes the environment. Like `setenv`, we store a copy instead.
+        let string = this.read_os_str_from_c_str(string_ptr)?.to_owned();
+        let string = string.to_str().ok_or_else(|| {
+            err_unsup_format!("`putenv` with a string that is not valid UTF-8 is not supported")
+        })?;
+        let (name, value) = match string.find('=') {
+            Some(idx) => (&string[..idx], Some(&string[idx + 1..])),
+            // Like glibc, we treat a string without `=` as a request to remove the variable.
+            None => (string, None),
+        };
+        if name.is_empty() {
+            let einval = this.eval_libc("EINVAL")?;
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+
+        let name = OsString::from(name);
+        let old = match value {
+            Some(value) => {
+                let var_ptr = alloc_env_var_as_c_str(&name, value.as_ref(), &mut this)?;
+                this.machine.env_vars.map.insert(name, var_ptr)
+            }
+            None => this.machine.env_vars.map.remove(&name),
+        };
+        if let Some(var) = old {
+            this.memory
+                .deallocate(var, None, MiriMemoryKind::Env.into())?;
+        }
+        this.update_environ()?;
+        Ok(0) // return zero on success
+    }
+
     #[allow(non_snake_case)]
     fn SetEnvironmentVariableW(
         &mut self,
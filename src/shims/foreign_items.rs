@@ -11,6 +11,8 @@ use rustc_apfloat::Float;
 use rustc_span::symbol::sym;
 use rustc_ast::attr;
 
+use rand::Rng;
+
 use crate::*;
 
 impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
@@ -44,13 +46,57 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         Align::from_bytes(prev_power_of_two(size)).unwrap()
     }
 
+    /// Records a heap allocation of `size` bytes, for `-Zmiri-heap-profile` and
+    /// `-Zmiri-heap-limit`.
+    fn track_heap_alloc(&mut self, ptr: Pointer<Tag>, size: u64) {
+        let this = self.eval_context_mut();
+        this.machine.heap_allocations.insert(ptr.alloc_id, size).unwrap_none();
+        this.machine.heap_live_bytes += size;
+        if let Some(profile) = this.machine.heap_profile.as_mut() {
+            profile.allocated(size);
+        }
+    }
+
+    /// Records that the allocation `ptr` points to was freed, for `-Zmiri-heap-profile` and
+    /// `-Zmiri-heap-limit`. Allocations that were not recorded by `track_heap_alloc` are ignored.
+    fn track_heap_dealloc(&mut self, ptr: Pointer<Tag>) {
+        let this = self.eval_context_mut();
+        if let Some(size) = this.machine.heap_allocations.remove(&ptr.alloc_id) {
+            this.machine.heap_live_bytes -= size;
+            if let Some(profile) = this.machine.heap_profile.as_mut() {
+                profile.deallocated(size);
+            }
+        }
+    }
+
+    /// Decides whether a heap allocation of `size` bytes should fail, because of
+    /// `-Zmiri-heap-limit` or `-Zmiri-oom-probability`. `old_ptr` points to the (live) allocation
+    /// that gets replaced, for reallocations.
+    fn heap_alloc_fails(&mut self, size: u64, old_ptr: Option<Pointer<Tag>>) -> bool {
+        let this = self.eval_context_mut();
+        if let Some(limit) = this.machine.heap_limit {
+            let old_size = old_ptr
+                .and_then(|ptr| this.machine.heap_allocations.get(&ptr.alloc_id))
+                .copied()
+                .unwrap_or(0);
+            // A size so large that this overflows certainly exceeds the limit.
+            let new_live_bytes = (this.machine.heap_live_bytes - old_size).checked_add(size);
+            if new_live_bytes.map_or(true, |live_bytes| live_bytes > limit) {
+                return true;
+            }
+        }
+        let probability = this.machine.oom_probability;
+        probability > 0.0 && this.memory.extra.rng.get_mut().gen_bool(probability)
+    }
+
     fn malloc(&mut self, size: u64, zero_init: bool, kind: MiriMemoryKind) -> Scalar<Tag> {
         let this = self.eval_context_mut();
-        if size == 0 {
+        if size == 0 || this.heap_alloc_fails(size, None) {
             Scalar::null_ptr(this)
         } else {
             let align = this.min_align(size, kind);
             let ptr = this.memory.allocate(Size::from_bytes(size), align, kind.into());
+            this.track_heap_alloc(ptr, size);
             if zero_init {
                 // We just allocated this, the access is definitely in-bounds.
                 this.memory.write_bytes(ptr.into(), iter::repeat(0u8).take(size as usize)).unwrap();
@@ -64,6 +110,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         if !this.is_null(ptr)? {
             let ptr = this.force_ptr(ptr)?;
             this.memory.deallocate(ptr, None, kind.into())?;
+            this.track_heap_dealloc(ptr);
         }
         Ok(())
     }
@@ -77,19 +124,27 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         let this = self.eval_context_mut();
         let new_align = this.min_align(new_size, kind);
         if this.is_null(old_ptr)? {
-            if new_size == 0 {
+            if new_size == 0 || this.heap_alloc_fails(new_size, None) {
                 Ok(Scalar::null_ptr(this))
             } else {
                 let new_ptr =
                     this.memory.allocate(Size::from_bytes(new_size), new_align, kind.into());
+                this.track_heap_alloc(new_ptr, new_size);
                 Ok(Scalar::Ptr(new_ptr))
             }
         } else {
             let old_ptr = this.force_ptr(old_ptr)?;
             if new_size == 0 {
                 this.memory.deallocate(old_ptr, None, kind.into())?;
+                this.track_heap_dealloc(old_ptr);
                 Ok(Scalar::null_ptr(this))
             } else {
+                // Only a live allocation may fail to be reallocated.
+                this.memory.get_size_and_align(old_ptr.alloc_id, AllocCheck::Live)?;
+                if this.heap_alloc_fails(new_size, Some(old_ptr)) {
+                    // The old allocation remains untouched.
+                    return Ok(Scalar::null_ptr(this));
+                }
                 let new_ptr = this.memory.reallocate(
                     old_ptr,
                     None,
@@ -97,6 +152,8 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     new_align,
                     kind.into(),
                 )?;
+                this.track_heap_dealloc(old_ptr);
+                this.track_heap_alloc(new_ptr, new_size);
                 Ok(Scalar::Ptr(new_ptr))
             }
         }
@@ -138,12 +195,33 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     return Ok(Some(&*this.load_mir(panic_impl_instance.def, None)?));
                 }
                 | "exit"
+                | "quick_exit"
+                => {
+                    let code = this.read_scalar(args[0])?.to_i32()?;
+                    // Unlike `_exit`, these run the handlers registered with `atexit` (or
+                    // `at_quick_exit`, respectively) first.
+                    this.run_atexit_handlers(/*quick_exit:*/ link_name == "quick_exit")?;
+                    throw_machine_stop!(TerminationInfo::Exit(code.into()));
+                }
+                | "_exit"
                 | "ExitProcess"
                 => {
                     // it's really u32 for ExitProcess, but we have to put it into the `Exit` variant anyway
                     let code = this.read_scalar(args[0])?.to_i32()?;
                     throw_machine_stop!(TerminationInfo::Exit(code.into()));
                 }
+                | "longjmp"
+                | "_longjmp"
+                => {
+                    this.longjmp(args[0], args[1])?;
+                    return Ok(None);
+                }
+                "abort" => {
+                    throw_machine_stop!(TerminationInfo::Abort(Some(format!("the program called `abort()`"))))
+                }
+                "__stack_chk_fail" => {
+                    throw_ub_format!("stack smashing detected by `__stack_chk_fail`")
+                }
                 _ => throw_unsup_format!("can't call (diverging) foreign function: {}", link_name),
             },
             Some(p) => p,
@@ -228,25 +306,35 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let size = this.read_scalar(args[0])?.to_machine_usize(this)?;
                 let align = this.read_scalar(args[1])?.to_machine_usize(this)?;
                 Self::check_alloc_request(size, align)?;
-                let ptr = this.memory.allocate(
-                    Size::from_bytes(size),
-                    Align::from_bytes(align).unwrap(),
-                    MiriMemoryKind::Rust.into(),
-                );
-                this.write_scalar(ptr, dest)?;
+                if this.heap_alloc_fails(size, None) {
+                    this.write_null(dest)?;
+                } else {
+                    let ptr = this.memory.allocate(
+                        Size::from_bytes(size),
+                        Align::from_bytes(align).unwrap(),
+                        MiriMemoryKind::Rust.into(),
+                    );
+                    this.track_heap_alloc(ptr, size);
+                    this.write_scalar(ptr, dest)?;
+                }
             }
             "__rust_alloc_zeroed" => {
                 let size = this.read_scalar(args[0])?.to_machine_usize(this)?;
                 let align = this.read_scalar(args[1])?.to_machine_usize(this)?;
                 Self::check_alloc_request(size, align)?;
-                let ptr = this.memory.allocate(
-                    Size::from_bytes(size),
-                    Align::from_bytes(align).unwrap(),
-                    MiriMemoryKind::Rust.into(),
-                );
-                // We just allocated this, the access is definitely in-bounds.
-                this.memory.write_bytes(ptr.into(), iter::repeat(0u8).take(usize::try_from(size).unwrap())).unwrap();
-                this.write_scalar(ptr, dest)?;
+                if this.heap_alloc_fails(size, None) {
+                    this.write_null(dest)?;
+                } else {
+                    let ptr = this.memory.allocate(
+                        Size::from_bytes(size),
+                        Align::from_bytes(align).unwrap(),
+                        MiriMemoryKind::Rust.into(),
+                    );
+                    this.track_heap_alloc(ptr, size);
+                    // We just allocated this, the access is definitely in-bounds.
+                    this.memory.write_bytes(ptr.into(), iter::repeat(0u8).take(usize::try_from(size).unwrap())).unwrap();
+                    this.write_scalar(ptr, dest)?;
+                }
             }
             "__rust_dealloc" => {
                 let ptr = this.read_scalar(args[0])?.not_undef()?;
@@ -259,6 +347,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     Some((Size::from_bytes(old_size), Align::from_bytes(align).unwrap())),
                     MiriMemoryKind::Rust.into(),
                 )?;
+                this.track_heap_dealloc(ptr);
             }
             "__rust_realloc" => {
                 let old_size = this.read_scalar(args[1])?.to_machine_usize(this)?;
@@ -267,15 +356,24 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 Self::check_alloc_request(new_size, align)?;
                 // No need to check old_size; we anyway check that they match the allocation.
                 let ptr = this.force_ptr(this.read_scalar(args[0])?.not_undef()?)?;
-                let align = Align::from_bytes(align).unwrap();
-                let new_ptr = this.memory.reallocate(
-                    ptr,
-                    Some((Size::from_bytes(old_size), align)),
-                    Size::from_bytes(new_size),
-                    align,
-                    MiriMemoryKind::Rust.into(),
-                )?;
-                this.write_scalar(new_ptr, dest)?;
+                // Only a live allocation may fail to be reallocated.
+                this.memory.get_size_and_align(ptr.alloc_id, AllocCheck::Live)?;
+                if this.heap_alloc_fails(new_size, Some(ptr)) {
+                    // The old allocation remains untouched.
+                    this.write_null(dest)?;
+                } else {
+                    let align = Align::from_bytes(align).unwrap();
+                    let new_ptr = this.memory.reallocate(
+                        ptr,
+                        Some((Size::from_bytes(old_size), align)),
+                        Size::from_bytes(new_size),
+                        align,
+                        MiriMemoryKind::Rust.into(),
+                    )?;
+                    this.track_heap_dealloc(ptr);
+                    this.track_heap_alloc(new_ptr, new_size);
+                    this.write_scalar(new_ptr, dest)?;
+                }
             }
 
             // C memory handling functions
@@ -298,6 +396,29 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
+            | "memcpy"
+            | "memmove"
+            => {
+                let dst = this.read_scalar(args[0])?.not_undef()?;
+                let src = this.read_scalar(args[1])?.not_undef()?;
+                let n = Size::from_bytes(this.read_scalar(args[2])?.to_machine_usize(this)?);
+                let align = Align::from_bytes(1).unwrap();
+                let dst_ptr = this.memory.check_ptr_access(dst, n, align)?;
+                let src_ptr = this.memory.check_ptr_access(src, n, align)?;
+
+                if let (Some(src_ptr), Some(dst_ptr)) = (src_ptr, dst_ptr) {
+                    if link_name == "memcpy"
+                        && src_ptr.alloc_id == dst_ptr.alloc_id
+                        && src_ptr.offset < dst_ptr.offset + n
+                        && dst_ptr.offset < src_ptr.offset + n
+                    {
+                        throw_ub_format!("memcpy: source and destination regions overlap; use memmove instead");
+                    }
+                    this.memory.copy(src_ptr, dst_ptr, n, /*nonoverlapping:*/ false)?;
+                }
+                // Both return their first argument.
+                this.write_scalar(dst, dest)?;
+            }
             "memrchr" => {
                 let ptr = this.read_scalar(args[0])?.not_undef()?;
                 let val = this.read_scalar(args[1])?.to_i32()? as u8;
@@ -331,6 +452,28 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     this.write_null(dest)?;
                 }
             }
+            "memmem" => {
+                let haystack = this.read_scalar(args[0])?.not_undef()?;
+                let haystack_len = this.read_scalar(args[1])?.to_machine_usize(this)?;
+                let needle = this.read_scalar(args[2])?.not_undef()?;
+                let needle_len = this.read_scalar(args[3])?.to_machine_usize(this)?;
+                let idx = {
+                    let haystack_bytes = this.memory.read_bytes(haystack, Size::from_bytes(haystack_len))?;
+                    let needle_bytes = this.memory.read_bytes(needle, Size::from_bytes(needle_len))?;
+                    if needle_bytes.is_empty() {
+                        // An empty needle is found at the start of the haystack.
+                        Some(0)
+                    } else {
+                        haystack_bytes.windows(needle_bytes.len()).position(|window| window == needle_bytes)
+                    }
+                };
+                if let Some(idx) = idx {
+                    let new_ptr = haystack.ptr_offset(Size::from_bytes(idx as u64), this)?;
+                    this.write_scalar(new_ptr, dest)?;
+                } else {
+                    this.write_null(dest)?;
+                }
+            }
             "strlen" => {
                 let ptr = this.read_scalar(args[0])?.not_undef()?;
                 let n = this.memory.read_c_str(ptr)?.len();
@@ -434,6 +577,17 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 this.write_scalar(Scalar::from_f64(res), dest)?;
             }
 
+            // Miri-specific functions
+            "miri_step_count" => {
+                this.write_scalar(Scalar::from_u64(this.machine.steps), dest)?;
+            }
+
+            // Architecture-specific shims
+            "llvm.x86.sse2.pause" if this.tcx.sess.target.target.arch == "x86" || this.tcx.sess.target.target.arch == "x86_64" => {
+                // This is the spin loop hint. There is no other thread that could make progress
+                // while we wait, so there is nothing to yield to.
+            }
+
             // Target-specific shims
             _ => match this.tcx.sess.target.target.target_os.as_str() {
                 "linux" | "macos" => return posix::EvalContextExt::emulate_foreign_item_by_name(this, link_name, args, dest, ret),
@@ -2,6 +2,8 @@ mod linux;
 mod macos;
 
 use std::convert::TryFrom;
+use std::ffi::OsStr;
+use std::iter;
 
 use log::trace;
 
@@ -34,6 +36,10 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let result = this.setenv(args[0], args[1])?;
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
+            "putenv" => {
+                let result = this.putenv(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "getcwd" => {
                 let result = this.getcwd(args[0], args[1])?;
                 this.write_scalar(result, dest)?;
@@ -52,6 +58,18 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let result = this.fcntl(args[0], args[1], args.get(2).cloned())?;
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
+            "dup" => {
+                let result = this.dup(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "dup2" => {
+                let result = this.dup2(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "pipe" => {
+                let result = this.pipe(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "read" => {
                 let result = this.read(args[0], args[1], args[2])?;
                 this.write_scalar(Scalar::from_machine_isize(result, this), dest)?;
@@ -61,7 +79,9 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let buf = this.read_scalar(args[1])?.not_undef()?;
                 let n = this.read_scalar(args[2])?.to_machine_usize(this)?;
                 trace!("Called write({:?}, {:?}, {:?})", fd, buf, n);
-                let result = if fd == 1 || fd == 2 {
+                let result = if (fd == 1 || fd == 2) && this.inject_eintr()? {
+                    -1
+                } else if fd == 1 || fd == 2 {
                     // stdout/stderr
                     use std::io::{self, Write};
 
@@ -119,6 +139,18 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 this.write_scalar(Scalar::from_i64(result), dest)?;
             }
 
+            // Time related shims
+            "nanosleep" => {
+                let result = this.nanosleep(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
+            // Memory mappings
+            "munmap" => {
+                let result = this.munmap(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
             // Allocation
             "posix_memalign" => {
                 let ret = this.deref_operand(args[0])?;
@@ -137,15 +169,21 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
                 if size == 0 {
                     this.write_null(ret.into())?;
+                    this.write_null(dest)?;
+                } else if this.heap_alloc_fails(size, None) {
+                    // `posix_memalign` does not set `errno`, it returns the error code.
+                    let enomem = this.eval_libc("ENOMEM")?;
+                    this.write_scalar(enomem, dest)?;
                 } else {
                     let ptr = this.memory.allocate(
                         Size::from_bytes(size),
                         Align::from_bytes(align).unwrap(),
                         MiriMemoryKind::C.into(),
                     );
+                    this.track_heap_alloc(ptr, size);
                     this.write_scalar(ptr, ret.into())?;
+                    this.write_null(dest)?;
                 }
-                this.write_null(dest)?;
             }
 
             // Dynamic symbol loading
@@ -167,9 +205,13 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             "sysconf" => {
                 let name = this.read_scalar(args[0])?.to_i32()?;
 
+                // `_SC_PAGE_SIZE` is a synonym for `_SC_PAGESIZE`, so it is covered as well.
                 let sysconfs = &[
                     ("_SC_PAGESIZE", Scalar::from_int(PAGE_SIZE, this.pointer_size())),
-                    ("_SC_NPROCESSORS_ONLN", Scalar::from_int(NUM_CPUS, this.pointer_size())),
+                    ("_SC_NPROCESSORS_ONLN", Scalar::from_int(this.machine.num_cpus, this.pointer_size())),
+                    ("_SC_NPROCESSORS_CONF", Scalar::from_int(this.machine.num_cpus, this.pointer_size())),
+                    ("_SC_OPEN_MAX", Scalar::from_int(1024, this.pointer_size())),
+                    ("_SC_CLK_TCK", Scalar::from_int(100, this.pointer_size())),
                 ];
                 let mut result = None;
                 for &(sysconf_name, value) in sysconfs {
@@ -182,7 +224,9 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 if let Some(result) = result {
                     this.write_scalar(result, dest)?;
                 } else {
-                    throw_unsup_format!("unimplemented sysconf name: {}", name)
+                    let einval = this.eval_libc("EINVAL")?;
+                    this.set_last_error(einval)?;
+                    this.write_scalar(Scalar::from_machine_isize(-1, this), dest)?;
                 }
             }
 
@@ -238,7 +282,89 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 throw_unsup_format!("Miri does not support threading");
             }
 
+            // Thread identification
+            "pthread_self" => {
+                // There is only the main thread, which we identify by `0`. We write a value of
+                // the full `pthread_t` size so that it round-trips through `pthread_t` variables.
+                this.write_null(dest)?;
+            }
+            "pthread_equal" => {
+                let t1 = this.read_scalar(args[0])?.not_undef()?;
+                let t2 = this.read_scalar(args[1])?.not_undef()?;
+                let t1 = this.force_bits(t1, args[0].layout.size)?;
+                let t2 = this.force_bits(t2, args[1].layout.size)?;
+                this.write_scalar(Scalar::from_i32((t1 == t2) as i32), dest)?;
+            }
+
+            // Scheduling
+            "sched_yield" => {
+                // There is no other thread we could yield to, so the current thread just continues.
+                this.write_null(dest)?;
+            }
+
+            // Thread attributes
+            "pthread_attr_init" => {
+                // The attributes are not stored anywhere, all attribute objects describe the
+                // main thread. We still initialize the memory so it can be copied around.
+                let attr_place = this.deref_operand(args[0])?;
+                let size = usize::try_from(attr_place.layout.size.bytes()).unwrap();
+                this.memory.write_bytes(attr_place.ptr, iter::repeat(0u8).take(size))?;
+                this.write_null(dest)?;
+            }
+            "pthread_attr_destroy" => {
+                let _attr_place = this.deref_operand(args[0])?;
+                this.write_null(dest)?;
+            }
+
+            // Process related shims
+            "getpid" => {
+                let result = this.getpid()?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "getppid" => {
+                let result = this.getppid()?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "setjmp" | "_setjmp" => {
+                let result = this.setjmp(args[0], dest, ret)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "atexit" => {
+                let func = this.read_scalar(args[0])?.not_undef()?;
+                let result = this.register_atexit_handler(func, None)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "__cxa_atexit" => {
+                let func = this.read_scalar(args[0])?.not_undef()?;
+                let arg = this.read_scalar(args[1])?.not_undef()?;
+                let _dso_handle = this.read_scalar(args[2])?.not_undef()?;
+                let result = this.register_atexit_handler(func, Some(arg))?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "at_quick_exit" => {
+                let func = this.read_scalar(args[0])?.not_undef()?;
+                let result = this.register_quick_exit_handler(func)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
             // Miscellaneous
+            "strerror" => {
+                let errnum = this.read_scalar(args[0])?.to_i32()?;
+                let ptr = match this.machine.strerror_messages.get(&errnum) {
+                    Some(&ptr) => ptr,
+                    None => {
+                        let (message, _known) = errno_message(this, errnum)?;
+                        let ptr = this.alloc_os_str_as_c_str(OsStr::new(&message), MiriMemoryKind::Machine.into());
+                        this.machine.strerror_messages.insert(errnum, ptr);
+                        ptr
+                    }
+                };
+                this.write_scalar(ptr.into(), dest)?;
+            }
+            "getentropy" => {
+                let result = this.getentropy(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "isatty" => {
                 let _fd = this.read_scalar(args[0])?.to_i32()?;
                 // "returns 1 if fd is an open file descriptor referring to a terminal; otherwise 0 is returned, and errno is set to indicate the error"
@@ -257,9 +383,6 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
             // Incomplete shims that we "stub out" just to get pre-main initialziation code to work.
             // These shims are enabled only when the caller is in the standard library.
-            | "pthread_attr_init"
-            | "pthread_attr_destroy"
-            | "pthread_self"
             | "pthread_attr_setstacksize" if this.frame().instance.to_string().starts_with("std::sys::unix::") => {
                 this.write_null(dest)?;
             }
@@ -289,6 +412,11 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             => {
                 this.write_null(dest)?;
             }
+            // Outside the standard library, `mprotect` is supported on `mmap`ed memory.
+            "mprotect" => {
+                let result = this.mprotect(args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
 
             // Platform-specific shims
             _ => {
@@ -303,3 +431,81 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         Ok(true)
     }
 }
+
+/// The messages that `strerror` reports for common `errno` values, as on Linux (glibc) and on
+/// macOS.
+const ERRNO_MESSAGES: &[(&str, &str, &str)] = &[
+    ("EPERM", "Operation not permitted", "Operation not permitted"),
+    ("ENOENT", "No such file or directory", "No such file or directory"),
+    ("ESRCH", "No such process", "No such process"),
+    ("EINTR", "Interrupted system call", "Interrupted system call"),
+    ("EIO", "Input/output error", "Input/output error"),
+    ("EBADF", "Bad file descriptor", "Bad file descriptor"),
+    ("EAGAIN", "Resource temporarily unavailable", "Resource temporarily unavailable"),
+    ("ENOMEM", "Cannot allocate memory", "Cannot allocate memory"),
+    ("EACCES", "Permission denied", "Permission denied"),
+    ("EFAULT", "Bad address", "Bad address"),
+    ("EEXIST", "File exists", "File exists"),
+    ("ENOTDIR", "Not a directory", "Not a directory"),
+    ("EISDIR", "Is a directory", "Is a directory"),
+    ("EINVAL", "Invalid argument", "Invalid argument"),
+    ("EMFILE", "Too many open files", "Too many open files"),
+    ("ENOSPC", "No space left on device", "No space left on device"),
+    ("EPIPE", "Broken pipe", "Broken pipe"),
+    ("ERANGE", "Numerical result out of range", "Result too large"),
+    ("ENOSYS", "Function not implemented", "Function not implemented"),
+    ("ENOTEMPTY", "Directory not empty", "Directory not empty"),
+    ("ETIMEDOUT", "Connection timed out", "Operation timed out"),
+    ("ECONNREFUSED", "Connection refused", "Connection refused"),
+];
+
+/// Returns the `strerror` message for `errnum`, and whether `errnum` is a known error number.
+fn errno_message<'tcx>(this: &mut MiriEvalContext<'_, 'tcx>, errnum: i32) -> InterpResult<'tcx, (String, bool)> {
+    let macos = this.tcx.sess.target.target.target_os == "macos";
+    if errnum == 0 {
+        let message = if macos { "Undefined error: 0" } else { "Success" };
+        return Ok((message.to_owned(), true));
+    }
+    for &(name, linux_message, macos_message) in ERRNO_MESSAGES {
+        if this.eval_libc_i32(name)? == errnum {
+            let message = if macos { macos_message } else { linux_message };
+            return Ok((message.to_owned(), true));
+        }
+    }
+    let message = if macos {
+        format!("Unknown error: {}", errnum)
+    } else {
+        format!("Unknown error {}", errnum)
+    };
+    Ok((message, false))
+}
+
+/// Shims the XSI-compliant `strerror_r`: writes the message for `errnum` into `buf`, truncating it
+/// if it does not fit. Returns 0 on success, `EINVAL` for unknown error numbers and `ERANGE` if
+/// the message was truncated.
+fn strerror_r<'tcx>(
+    this: &mut MiriEvalContext<'_, 'tcx>,
+    errnum_op: OpTy<'tcx, Tag>,
+    buf_op: OpTy<'tcx, Tag>,
+    buflen_op: OpTy<'tcx, Tag>,
+) -> InterpResult<'tcx, i32> {
+    let errnum = this.read_scalar(errnum_op)?.to_i32()?;
+    let buf = this.read_scalar(buf_op)?.not_undef()?;
+    let buflen = this.read_scalar(buflen_op)?.to_machine_usize(this)?;
+
+    let (message, known) = errno_message(this, errnum)?;
+    let message = message.as_bytes();
+    if buflen == 0 {
+        return this.eval_libc_i32("ERANGE");
+    }
+    // Leave room for the null terminator.
+    let len = message.len().min(usize::try_from(buflen - 1).unwrap_or(usize::MAX));
+    this.memory.write_bytes(buf, message[..len].iter().copied().chain(iter::once(0u8)))?;
+    if !known {
+        this.eval_libc_i32("EINVAL")
+    } else if len < message.len() {
+        this.eval_libc_i32("ERANGE")
+    } else {
+        Ok(0)
+    }
+}
@@ -1,5 +1,11 @@
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+use std::iter;
+
 use crate::*;
 use rustc_middle::mir;
+use rustc_middle::ty::layout::{Align, Size};
+use rustc_target::abi::LayoutOf;
 
 impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
 pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
@@ -26,6 +32,18 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let result = this.close(args[0])?;
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
+            "stat" | "stat64" => {
+                let result = this.linux_stat(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "lstat" | "lstat64" => {
+                let result = this.linux_lstat(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "fstat" | "fstat64" => {
+                let result = this.linux_fstat(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "opendir" => {
                 let result = this.opendir(args[0])?;
                 this.write_scalar(result, dest)?;
@@ -35,6 +53,14 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
             // Linux-only
+            "dup3" => {
+                let result = this.dup3(args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "pipe2" => {
+                let result = this.pipe2(args[0], args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "posix_fadvise" => {
                 let _fd = this.read_scalar(args[0])?.to_i32()?;
                 let _offset = this.read_scalar(args[1])?.to_machine_isize(this)?;
@@ -44,14 +70,83 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 this.write_null(dest)?;
             }
 
+            // Message queues
+            "mq_open" => {
+                // The mode (`args[2]`) is only passed with `O_CREAT`, and then comes the attributes.
+                let result = this.mq_open(args[0], args[1], args.get(3).cloned())?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "mq_close" => {
+                let result = this.mq_close(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "mq_unlink" => {
+                let result = this.mq_unlink(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "mq_send" | "mq_timedsend" => {
+                let result = this.mq_send(args[0], args[1], args[2], args[3], args.get(4).cloned())?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "mq_receive" | "mq_timedreceive" => {
+                let result = this.mq_receive(args[0], args[1], args[2], args[3], args.get(4).cloned())?;
+                this.write_scalar(Scalar::from_machine_isize(result, this), dest)?;
+            }
+            "mq_getattr" => {
+                let result = this.mq_getsetattr(args[0], None, args[1])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "mq_setattr" => {
+                let result = this.mq_getsetattr(args[0], Some(args[1]), args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
             // Time related shims
             "clock_gettime" => {
                 // This is a POSIX function but it has only been tested on linux.
                 let result = this.clock_gettime(args[0], args[1])?;
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
+            "clock_nanosleep" => {
+                let result = this.clock_nanosleep(args[0], args[1], args[2], args[3])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
+            // Memory mappings
+            "mmap" | "mmap64" => {
+                let result = this.mmap(args[0], args[1], args[2], args[3], args[4], args[5])?;
+                this.write_scalar(result, dest)?;
+            }
 
             // Querying system information
+            "pthread_getattr_np" => {
+                let thread = this.read_scalar(args[0])?.to_machine_usize(this)?;
+                let attr_place = this.deref_operand(args[1])?;
+                // The main thread, identified by `0`, is the only thread.
+                if thread != 0 {
+                    let esrch = this.eval_libc("ESRCH")?;
+                    this.write_scalar(esrch, dest)?;
+                } else {
+                    // Attribute objects do not store anything, so we only initialize the memory.
+                    let size = usize::try_from(attr_place.layout.size.bytes()).unwrap();
+                    this.memory.write_bytes(attr_place.ptr, iter::repeat(0u8).take(size))?;
+                    this.write_null(dest)?;
+                }
+            }
+            "pthread_attr_getstacksize" => {
+                let _attr_place = this.deref_operand(args[0])?;
+                let size_place = this.deref_operand(args[1])?;
+                this.write_scalar(Scalar::from_uint(STACK_SIZE, this.pointer_size()), size_place.into())?;
+                this.write_null(dest)?;
+            }
+            "pthread_attr_getdetachstate" => {
+                let _attr_place = this.deref_operand(args[0])?;
+                let state_place = this.deref_operand(args[1])?;
+                // The main thread cannot be detached.
+                let joinable = this.eval_libc("PTHREAD_CREATE_JOINABLE")?;
+                this.write_scalar(joinable, state_place.into())?;
+                this.write_null(dest)?;
+            }
             "pthread_attr_getstack" => {
                 // We don't support "pthread_attr_setstack", so we just pretend all stacks have the same values here.
                 let _attr_place = this.deref_operand(args[0])?;
@@ -70,6 +165,14 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // Return success (`0`).
                 this.write_null(dest)?;
             }
+            "uname" => {
+                let result = uname(this, args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "gettid" => {
+                let result = this.linux_gettid()?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
 
             // Dynamically invoked syscalls
             "syscall" => {
@@ -81,7 +184,16 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     .eval_libc("SYS_statx")?
                     .to_machine_usize(this)?;
 
-                match this.read_scalar(args[0])?.to_machine_usize(this)? {
+                let sys_gettid = this
+                    .eval_libc("SYS_gettid")?
+                    .to_machine_usize(this)?;
+
+                let id = this.read_scalar(args[0])?.to_machine_usize(this)?;
+                // Not every version of `libc` knows the `io_uring` syscalls, so they get looked
+                // up separately.
+                let io_uring = this.is_io_uring_syscall(id)?;
+
+                match id {
                     // `libc::syscall(NR_GETRANDOM, buf.as_mut_ptr(), buf.len(), GRND_NONBLOCK)`
                     // is called if a `HashMap` is created the regular way (e.g. HashMap<K, V>).
                     id if id == sys_getrandom => {
@@ -95,6 +207,15 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                         let result = this.linux_statx(args[1], args[2], args[3], args[4], args[5])?;
                         this.write_scalar(Scalar::from_machine_isize(result.into(), this), dest)?;
                     }
+                    // `gettid` has no glibc wrapper before glibc 2.30.
+                    id if id == sys_gettid => {
+                        let result = this.linux_gettid()?;
+                        this.write_scalar(Scalar::from_machine_isize(result.into(), this), dest)?;
+                    }
+                    id if io_uring => {
+                        let result = this.io_uring_syscall(id)?;
+                        this.write_scalar(Scalar::from_machine_isize(result, this), dest)?;
+                    }
                     id => throw_unsup_format!("miri does not support syscall ID {}", id),
                 }
             }
@@ -103,19 +224,24 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             "getrandom" => {
                 getrandom(this, args, dest)?;
             }
+            "prctl" => {
+                let result = prctl(this, args)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "__xpg_strerror_r" => {
+                let result = super::strerror_r(this, args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "sched_getaffinity" => {
-                let _pid = this.read_scalar(args[0])?.to_i32()?;
-                let _cpusetsize = this.read_scalar(args[1])?.to_machine_usize(this)?;
-                let _mask = this.deref_operand(args[2])?;
-                // FIXME: we just return an error; `num_cpus` then falls back to `sysconf`.
-                let einval = this.eval_libc("EINVAL")?;
-                this.set_last_error(einval)?;
-                this.write_scalar(Scalar::from_i32(-1), dest)?;
+                let result = sched_getaffinity(this, args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
             }
-
-            // Incomplete shims that we "stub out" just to get pre-main initialziation code to work.
-            // These shims are enabled only when the caller is in the standard library.
-            "pthread_getattr_np" if this.frame().instance.to_string().starts_with("std::sys::unix::") => {
+            "sched_setaffinity" => {
+                let _pid = this.read_scalar(args[0])?.to_i32()?;
+                let cpusetsize = this.read_scalar(args[1])?.to_machine_usize(this)?;
+                let mask = this.read_scalar(args[2])?.not_undef()?;
+                // There is nothing to pin the thread to, but make sure the mask is readable.
+                this.memory.read_bytes(mask, Size::from_bytes(cpusetsize))?;
                 this.write_null(dest)?;
             }
 
@@ -143,3 +269,152 @@ fn getrandom<'tcx>(
     this.write_scalar(Scalar::from_machine_usize(len, this), dest)?;
     Ok(())
 }
+
+// Shims the linux `prctl` function. Options that would change the security settings of the process
+// fail with `EINVAL`, as if the kernel did not support them.
+fn prctl<'tcx>(
+    this: &mut MiriEvalContext<'_, 'tcx>,
+    args: &[OpTy<'tcx, Tag>],
+) -> InterpResult<'tcx, i32> {
+    let option = this.read_scalar(args[0])?.to_i32()?;
+
+    if option == this.eval_libc_i32("PR_GET_DUMPABLE")? {
+        return Ok(this.machine.dumpable.into());
+    }
+    if option == this.eval_libc_i32("PR_SET_DUMPABLE")? {
+        match this.read_scalar(args[1])?.to_machine_usize(this)? {
+            0 => this.machine.dumpable = false,
+            1 => this.machine.dumpable = true,
+            _ => {
+                let einval = this.eval_libc("EINVAL")?;
+                this.set_last_error(einval)?;
+                return Ok(-1);
+            }
+        }
+        return Ok(0);
+    }
+
+    let unsupported = &["PR_SET_SECCOMP", "PR_SET_CHILD_SUBREAPER", "PR_CAP_AMBIENT", "PR_SET_NO_NEW_PRIVS"];
+    let mut known = false;
+    for name in unsupported {
+        if option == this.eval_libc_i32(name)? {
+            known = true;
+            break;
+        }
+    }
+    if !known {
+        register_diagnostic(NonHaltingDiagnostic::UnsupportedPrctlOption(option));
+    }
+    let einval = this.eval_libc("EINVAL")?;
+    this.set_last_error(einval)?;
+    Ok(-1)
+}
+
+// Shims the linux `sched_getaffinity` function. The mask contains the first `-Zmiri-num-cpus` CPUs,
+// which is consistent with what `sysconf(_SC_NPROCESSORS_ONLN)` reports. When isolation is disabled,
+// the mask of the host process is reported instead, if the host is Linux.
+fn sched_getaffinity<'tcx>(
+    this: &mut MiriEvalContext<'_, 'tcx>,
+    pid_op: OpTy<'tcx, Tag>,
+    cpusetsize_op: OpTy<'tcx, Tag>,
+    mask_op: OpTy<'tcx, Tag>,
+) -> InterpResult<'tcx, i32> {
+    // There is only one thread, so `pid` can only refer to it: either as 0 or as our process ID.
+    let pid = this.read_scalar(pid_op)?.to_i32()?;
+    let cpusetsize = this.read_scalar(cpusetsize_op)?.to_machine_usize(this)?;
+    let mask = this.read_scalar(mask_op)?.not_undef()?;
+
+    if pid != 0 && pid != this.getpid()? {
+        let esrch = this.eval_libc("ESRCH")?;
+        this.set_last_error(esrch)?;
+        return Ok(-1);
+    }
+
+    let host_cpus = if this.machine.communicate { host_cpu_affinity() } else { None };
+    let cpus = host_cpus.unwrap_or_else(|| (0..u64::from(this.machine.num_cpus)).collect());
+
+    // Like the kernel, we require the mask to consist of whole words that fit all CPUs.
+    let word_size = this.pointer_size().bytes();
+    let word_bits = word_size * 8;
+    let num_bits = cpus.iter().max().map_or(0, |&cpu| cpu + 1);
+    if cpusetsize % word_size != 0 || cpusetsize.saturating_mul(8) < num_bits {
+        let einval = this.eval_libc("EINVAL")?;
+        this.set_last_error(einval)?;
+        return Ok(-1);
+    }
+
+    // Check that the *entire* mask is actually valid memory, and clear it.
+    this.memory.check_ptr_access(
+        mask,
+        Size::from_bytes(cpusetsize),
+        Align::from_bytes(1).unwrap(),
+    )?;
+    this.memory.write_bytes(mask, iter::repeat(0u8).take(usize::try_from(cpusetsize).unwrap()))?;
+
+    // Then set the bits of the CPUs, which only involves the words up to the highest CPU.
+    let mut words = vec![0u128; usize::try_from((num_bits + word_bits - 1) / word_bits).unwrap()];
+    for cpu in cpus {
+        words[usize::try_from(cpu / word_bits).unwrap()] |= 1 << (cpu % word_bits);
+    }
+    let mask = this.force_ptr(mask)?;
+    let word_layout = this.layout_of(this.tcx.types.usize)?;
+    for (word_idx, word) in (0..).zip(words) {
+        let word_ptr = mask.offset(Size::from_bytes(word_idx * word_size), this)?;
+        let word_place = MPlaceTy::from_aligned_ptr(word_ptr, word_layout);
+        this.write_scalar(Scalar::from_uint(word, this.pointer_size()), word_place.into())?;
+    }
+
+    Ok(0)
+}
+
+// Returns the CPUs that the Miri process may run on, as read from `/proc/self/status`, or `None` if
+// that is not available (e.g. because the host is not Linux).
+fn host_cpu_affinity() -> Option<Vec<u64>> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let prefix = "Cpus_allowed:";
+    let mask = status.lines().find(|line| line.starts_with(prefix))?[prefix.len()..].trim();
+    // The mask is written in hex, in comma-separated groups of 32 CPUs, the last group first.
+    let mut cpus = Vec::new();
+    for (group_idx, group) in (0u64..).zip(mask.rsplit(',')) {
+        let group = u32::from_str_radix(group, 16).ok()?;
+        cpus.extend((0..32).filter(|bit| group & (1 << bit) != 0).map(|bit| group_idx * 32 + bit));
+    }
+    Some(cpus)
+}
+
+// Shims the linux `uname` function. We always report the same, made-up system so that the
+// interpreted program behaves the same on every host.
+fn uname<'tcx>(
+    this: &mut MiriEvalContext<'_, 'tcx>,
+    buf_op: OpTy<'tcx, Tag>,
+) -> InterpResult<'tcx, i32> {
+    if this.is_null(this.read_scalar(buf_op)?.not_undef()?)? {
+        let efault = this.eval_libc("EFAULT")?;
+        this.set_last_error(efault)?;
+        return Ok(-1);
+    }
+    let buf_place = this.deref_operand(buf_op)?;
+
+    let machine = match this.tcx.sess.target.target.arch.as_str() {
+        "x86" => "i686".to_owned(),
+        arch => arch.to_owned(),
+    };
+    let release = this.machine.kernel_release.clone();
+    // `sysname`, `nodename`, `release`, `version` and `machine`; glibc also has `domainname`.
+    let values = ["Linux", "miri", release.as_str(), "#1 Miri", machine.as_str(), "(none)"];
+
+    for idx in 0..buf_place.layout.fields.count() {
+        let field_place = this.mplace_field(buf_place, idx as u64)?;
+        let value = values.get(idx).copied().unwrap_or("");
+        let (written, _) = this.write_os_str_to_c_str(
+            OsStr::new(value),
+            field_place.ptr,
+            field_place.layout.size.bytes(),
+        )?;
+        if !written {
+            throw_unsup_format!("`uname` field {} does not fit value {:?}", idx, value);
+        }
+    }
+
+    Ok(0)
+}
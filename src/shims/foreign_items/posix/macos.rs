@@ -8,7 +8,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         link_name: &str,
         args: &[OpTy<'tcx, Tag>],
         dest: PlaceTy<'tcx, Tag>,
-        _ret: mir::BasicBlock,
+        ret: mir::BasicBlock,
     ) -> InterpResult<'tcx, bool> {
         let this = self.eval_context_mut();
 
@@ -76,6 +76,51 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 this.machine.tls.set_global_dtor(dtor, data)?;
             }
 
+            // Synchronization primitives
+            "os_unfair_lock_lock" => {
+                this.os_unfair_lock_lock(args[0])?;
+            }
+            "os_unfair_lock_trylock" => {
+                let result = this.os_unfair_lock_trylock(args[0])?;
+                this.write_scalar(Scalar::from_bool(result), dest)?;
+            }
+            "os_unfair_lock_unlock" => {
+                this.os_unfair_lock_unlock(args[0])?;
+            }
+            "os_unfair_lock_assert_owner" => {
+                this.os_unfair_lock_assert_owner(args[0])?;
+            }
+            "os_unfair_lock_assert_not_owner" => {
+                this.os_unfair_lock_assert_not_owner(args[0])?;
+            }
+            "dispatch_semaphore_create" => {
+                let result = this.dispatch_semaphore_create(args[0])?;
+                this.write_scalar(result, dest)?;
+            }
+            "dispatch_semaphore_wait" => {
+                let result = this.dispatch_semaphore_wait(args[0], args[1])?;
+                this.write_scalar(Scalar::from_machine_isize(result, this), dest)?;
+            }
+            "dispatch_semaphore_signal" => {
+                let result = this.dispatch_semaphore_signal(args[0])?;
+                this.write_scalar(Scalar::from_machine_isize(result, this), dest)?;
+            }
+            "dispatch_once_f" => {
+                return this.dispatch_once_f(args[0], args[1], args[2], ret);
+            }
+            "dispatch_retain" => {
+                this.dispatch_retain(args[0])?;
+            }
+            "dispatch_release" => {
+                this.dispatch_release(args[0])?;
+            }
+
+            // Miscellaneous
+            "strerror_r" => {
+                let result = super::strerror_r(this, args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
             // Querying system information
             "pthread_get_stackaddr_np" => {
                 let _thread = this.read_scalar(args[0])?.not_undef()?;
@@ -95,6 +140,10 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let addr = this.read_scalar(args[0])?.not_undef()?;
                 this.write_scalar(addr, dest)?;
             }
+            "mmap" => {
+                let result = this.mmap(args[0], args[1], args[2], args[3], args[4], args[5])?;
+                this.write_scalar(result, dest)?;
+            }
 
             _ => throw_unsup_format!("can't call foreign function: {}", link_name),
         };
@@ -10,7 +10,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         link_name: &str,
         args: &[OpTy<'tcx, Tag>],
         dest: PlaceTy<'tcx, Tag>,
-        _ret: mir::BasicBlock,
+        ret: mir::BasicBlock,
     ) -> InterpResult<'tcx, bool> {
         let this = self.eval_context_mut();
 
@@ -132,9 +132,8 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     iter::repeat(0u8).take(system_info.layout.size.bytes() as usize),
                 )?;
                 // Set number of processors.
-                let dword_size = Size::from_bytes(4);
                 let num_cpus = this.mplace_field(system_info, 6)?;
-                this.write_scalar(Scalar::from_int(NUM_CPUS, dword_size), num_cpus.into())?;
+                this.write_scalar(Scalar::from_u32(this.machine.num_cpus), num_cpus.into())?;
             }
 
             // Thread-local storage
@@ -158,6 +157,73 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // Return success (`1`).
                 this.write_scalar(Scalar::from_i32(1), dest)?;
             }
+            "TlsFree" => {
+                let key = u128::from(this.read_scalar(args[0])?.to_u32()?);
+                this.machine.tls.delete_tls_key(key)?;
+
+                // Return success (`1`).
+                this.write_scalar(Scalar::from_i32(1), dest)?;
+            }
+
+            // Time related shims
+            "Sleep" => {
+                this.Sleep(args[0])?;
+                // Nothing to do, not even a return value.
+            }
+            "SleepEx" => {
+                let alertable = this.read_scalar(args[1])?.to_i32()?;
+                if alertable != 0 {
+                    throw_unsup_format!("alertable `SleepEx` is not supported");
+                }
+                this.Sleep(args[0])?;
+                // Non-alertable sleeps always run to completion.
+                this.write_scalar(Scalar::from_u32(0), dest)?;
+            }
+
+            // Synchronization primitives
+            "InitOnceExecuteOnce" => {
+                return this.InitOnceExecuteOnce(args[0], args[1], args[2], args[3], dest, ret);
+            }
+            "InitOnceBeginInitialize" => {
+                let result = this.InitOnceBeginInitialize(args[0], args[1], args[2], args[3])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "InitOnceComplete" => {
+                let result = this.InitOnceComplete(args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            | "InterlockedExchange"
+            | "InterlockedExchange64"
+            | "InterlockedExchangeAdd"
+            | "InterlockedExchangeAdd64"
+            | "InterlockedIncrement"
+            | "InterlockedIncrement64"
+            | "InterlockedDecrement"
+            | "InterlockedDecrement64"
+            | "InterlockedCompareExchange"
+            | "InterlockedCompareExchange64" => {
+                this.windows_interlocked(link_name, args, dest)?;
+            }
+            "CreateMutexW" => {
+                let handle = this.CreateMutexW(args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_machine_isize(handle, this), dest)?;
+            }
+            "OpenMutexW" => {
+                let handle = this.OpenMutexW(args[0], args[1], args[2])?;
+                this.write_scalar(Scalar::from_machine_isize(handle, this), dest)?;
+            }
+            "WaitForSingleObject" => {
+                let result = this.WaitForSingleObject(args[0], args[1])?;
+                this.write_scalar(Scalar::from_u32(result), dest)?;
+            }
+            "ReleaseMutex" => {
+                let result = this.ReleaseMutex(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "CloseHandle" => {
+                let result = this.CloseHandle(args[0])?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
 
             // Access to command-line arguments
             "GetCommandLineW" => {
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::fs::{read_dir, remove_dir, remove_file, rename, DirBuilder, File, FileType, OpenOptions, ReadDir};
 use std::io::{Read, Seek, SeekFrom, Write};
@@ -14,14 +14,30 @@ use helpers::{immty_from_int_checked, immty_from_uint_checked};
 use shims::time::system_time_to_duration;
 
 #[derive(Debug)]
-pub struct FileHandle {
-    file: File,
-    writable: bool,
+pub enum FileHandle {
+    /// A file of the host.
+    File { file: File, writable: bool },
+    /// The read end or the write end (if `writable`) of the pipe whose buffer is stored in
+    /// `FileHandler::pipes` under `pipe`.
+    Pipe { pipe: u64, writable: bool },
+}
+
+impl FileHandle {
+    fn try_clone(&self) -> std::io::Result<FileHandle> {
+        match *self {
+            FileHandle::File { ref file, writable } =>
+                Ok(FileHandle::File { file: file.try_clone()?, writable }),
+            FileHandle::Pipe { pipe, writable } => Ok(FileHandle::Pipe { pipe, writable }),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct FileHandler {
     handles: BTreeMap<i32, FileHandle>,
+    /// The bytes that were written to each pipe and not read yet.
+    pipes: BTreeMap<u64, VecDeque<u8>>,
+    next_pipe: u64,
 }
 
 // fd numbers 0, 1, and 2 are reserved for stdin, stdout, and stderr
@@ -62,6 +78,27 @@ impl FileHandler {
         self.handles.insert(new_fd, file_handle).unwrap_none();
         new_fd
     }
+
+    fn is_pipe(&self, fd: i32) -> bool {
+        matches!(self.handles.get(&fd), Some(FileHandle::Pipe { .. }))
+    }
+
+    /// Returns whether some fd still refers to the write end (if `writable`) or the read end of
+    /// `pipe`.
+    fn pipe_end_is_open(&self, pipe: u64, writable: bool) -> bool {
+        self.handles.values().any(|file_handle| match *file_handle {
+            FileHandle::Pipe { pipe: other, writable: other_writable } =>
+                other == pipe && other_writable == writable,
+            FileHandle::File { .. } => false,
+        })
+    }
+
+    /// Frees the buffer of `pipe` once neither of its ends is open anymore.
+    fn release_pipe(&mut self, pipe: u64) {
+        if !self.pipe_end_is_open(pipe, false) && !self.pipe_end_is_open(pipe, true) {
+            self.pipes.remove(&pipe);
+        }
+    }
 }
 
 impl<'mir, 'tcx> EvalContextExtPrivate<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
@@ -117,8 +154,8 @@ trait EvalContextExtPrivate<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, '
         let imms = [
             immty_from_uint_checked(0u128, dev_t_layout)?, // st_dev
             immty_from_uint_checked(mode, mode_t_layout)?, // st_mode
-            immty_from_uint_checked(0u128, nlink_t_layout)?, // st_nlink
-            immty_from_uint_checked(0u128, ino_t_layout)?, // st_ino
+            immty_from_uint_checked(metadata.nlink, nlink_t_layout)?, // st_nlink
+            immty_from_uint_checked(metadata.ino, ino_t_layout)?, // st_ino
             immty_from_uint_checked(0u128, uid_t_layout)?, // st_uid
             immty_from_uint_checked(0u128, gid_t_layout)?, // st_gid
             immty_from_uint_checked(0u128, dev_t_layout)?, // st_rdev
@@ -144,6 +181,85 @@ trait EvalContextExtPrivate<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, '
         Ok(0)
     }
 
+    /// Emulate `stat` or `lstat` on `linux`. This function is not intended to be
+    /// called directly from `emulate_foreign_item_by_name`, so it does not check if isolation is
+    /// disabled or if the target OS is the correct one. Please use `linux_stat` or
+    /// `linux_lstat` instead.
+    fn linux_stat_or_lstat(
+        &mut self,
+        follow_symlink: bool,
+        path_op: OpTy<'tcx, Tag>,
+        buf_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let path_scalar = this.read_scalar(path_op)?.not_undef()?;
+        let path = this.read_path_from_c_str(path_scalar)?.into_owned();
+
+        let metadata = match FileMetadata::from_path(this, &path, follow_symlink)? {
+            Some(metadata) => metadata,
+            None => return Ok(-1),
+        };
+        this.linux_stat_write_buf(metadata, buf_op)
+    }
+
+    fn linux_stat_write_buf(
+        &mut self,
+        metadata: FileMetadata,
+        buf_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        // The layout of `struct stat` differs between architectures; we only know the one
+        // used on x86_64.
+        if this.tcx.sess.target.target.arch != "x86_64" {
+            throw_unsup_format!("`stat` is only supported on x86_64 Linux targets");
+        }
+
+        let mode: u32 = metadata.mode.to_u32()?;
+
+        let (access_sec, access_nsec) = metadata.accessed.unwrap_or((0, 0));
+        let (modified_sec, modified_nsec) = metadata.modified.unwrap_or((0, 0));
+
+        let dev_t_layout = this.libc_ty_layout("dev_t")?;
+        let mode_t_layout = this.libc_ty_layout("mode_t")?;
+        let nlink_t_layout = this.libc_ty_layout("nlink_t")?;
+        let ino_t_layout = this.libc_ty_layout("ino_t")?;
+        let uid_t_layout = this.libc_ty_layout("uid_t")?;
+        let gid_t_layout = this.libc_ty_layout("gid_t")?;
+        let time_t_layout = this.libc_ty_layout("time_t")?;
+        let long_layout = this.libc_ty_layout("c_long")?;
+        let off_t_layout = this.libc_ty_layout("off_t")?;
+        let blkcnt_t_layout = this.libc_ty_layout("blkcnt_t")?;
+        let blksize_t_layout = this.libc_ty_layout("blksize_t")?;
+        let int_layout = this.libc_ty_layout("c_int")?;
+
+        let imms = [
+            immty_from_uint_checked(0u128, dev_t_layout)?, // st_dev
+            immty_from_uint_checked(metadata.ino, ino_t_layout)?, // st_ino
+            immty_from_uint_checked(metadata.nlink, nlink_t_layout)?, // st_nlink
+            immty_from_uint_checked(mode, mode_t_layout)?, // st_mode
+            immty_from_uint_checked(0u128, uid_t_layout)?, // st_uid
+            immty_from_uint_checked(0u128, gid_t_layout)?, // st_gid
+            immty_from_int_checked(0i128, int_layout)?, // __pad0
+            immty_from_uint_checked(0u128, dev_t_layout)?, // st_rdev
+            immty_from_uint_checked(metadata.size, off_t_layout)?, // st_size
+            immty_from_uint_checked(0u128, blksize_t_layout)?, // st_blksize
+            immty_from_uint_checked(0u128, blkcnt_t_layout)?, // st_blocks
+            immty_from_uint_checked(access_sec, time_t_layout)?, // st_atime
+            immty_from_uint_checked(access_nsec, long_layout)?, // st_atime_nsec
+            immty_from_uint_checked(modified_sec, time_t_layout)?, // st_mtime
+            immty_from_uint_checked(modified_nsec, long_layout)?, // st_mtime_nsec
+            immty_from_uint_checked(0u128, time_t_layout)?, // st_ctime
+            immty_from_uint_checked(0u128, long_layout)?, // st_ctime_nsec
+        ];
+
+        let buf = this.deref_operand(buf_op)?;
+        this.write_packed_immediates(buf, &imms)?;
+
+        Ok(0)
+    }
+
     /// Function used when a handle is not found inside `FileHandler`. It returns `Ok(-1)`and sets
     /// the last OS error to `libc::EBADF` (invalid file descriptor). This function uses
     /// `T: From<i32>` instead of `i32` directly because some fs functions return different integer
@@ -155,6 +271,97 @@ trait EvalContextExtPrivate<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, '
         Ok((-1).into())
     }
 
+    /// Reads up to `count` bytes from `pipe` into `buf`. Reading from an empty pipe returns 0 (end
+    /// of file) once the write end is closed. While the write end is open, the read would block
+    /// until some other thread writes, which can never happen, so this is a deadlock.
+    fn read_from_pipe(
+        &mut self,
+        pipe: u64,
+        writable: bool,
+        buf: Scalar<Tag>,
+        count: u64,
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+
+        // Only the read end can be read from.
+        if writable {
+            return this.handle_not_found();
+        }
+        let fh = &mut this.machine.file_handler;
+        if count > 0 && fh.pipes[&pipe].is_empty() {
+            if fh.pipe_end_is_open(pipe, /*writable:*/ true) {
+                throw_machine_stop!(TerminationInfo::Deadlock);
+            }
+            return Ok(0);
+        }
+        let buffer = fh.pipes.get_mut(&pipe).unwrap();
+        let read_bytes = buffer.len().min(usize::try_from(count).unwrap());
+        let bytes: Vec<u8> = buffer.drain(..read_bytes).collect();
+        this.memory.write_bytes(buf, bytes)?;
+        Ok(i64::try_from(read_bytes).unwrap())
+    }
+
+    /// Writes up to `count` bytes from `buf` into `pipe`, as many as fit into the pipe capacity.
+    /// Writing fails with `EPIPE` once the read end is closed. While the read end is open, writing
+    /// to a full pipe would block until some other thread reads, so this is a deadlock.
+    fn write_to_pipe(
+        &mut self,
+        pipe: u64,
+        writable: bool,
+        buf: Scalar<Tag>,
+        count: u64,
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+
+        // Only the write end can be written to.
+        if !writable {
+            return this.handle_not_found();
+        }
+        if !this.machine.file_handler.pipe_end_is_open(pipe, /*writable:*/ false) {
+            let epipe = this.eval_libc("EPIPE")?;
+            this.set_last_error(epipe)?;
+            return Ok(-1);
+        }
+        let buffered = u64::try_from(this.machine.file_handler.pipes[&pipe].len()).unwrap();
+        let space = this.machine.pipe_capacity.saturating_sub(buffered);
+        if count > 0 && space == 0 {
+            throw_machine_stop!(TerminationInfo::Deadlock);
+        }
+        let written_bytes = count.min(space);
+        let bytes = this.memory.read_bytes(buf, Size::from_bytes(written_bytes))?;
+        this.machine.file_handler.pipes.get_mut(&pipe).unwrap().extend(bytes);
+        Ok(i64::try_from(written_bytes).unwrap())
+    }
+
+    /// Duplicates `old_fd` into the lowest available fd, or into `new_fd` (which gets closed
+    /// first if it is open). This function does not check if isolation is disabled.
+    fn dup_fd(&mut self, old_fd: i32, new_fd: Option<i32>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        if (0..MIN_NORMAL_FILE_FD).contains(&old_fd) || new_fd.map_or(false, |fd| (0..MIN_NORMAL_FILE_FD).contains(&fd)) {
+            throw_unsup_format!("duplicating file descriptors for stdin, stdout, or stderr is not supported")
+        }
+        let fh = &mut this.machine.file_handler;
+        let file_result = match fh.handles.get(&old_fd) {
+            Some(file_handle) => file_handle.try_clone(),
+            None => return this.handle_not_found(),
+        };
+        let fd_result = match new_fd {
+            None => file_result.map(|duplicated| fh.insert_fd(duplicated)),
+            Some(new_fd) if new_fd < 0 => return this.handle_not_found(),
+            // Duplicating a file descriptor into itself does nothing.
+            Some(new_fd) if new_fd == old_fd => return Ok(new_fd),
+            Some(new_fd) => file_result.map(|duplicated| {
+                // Any file previously open under `new_fd` is closed, ignoring errors.
+                if let Some(FileHandle::Pipe { pipe, .. }) = fh.handles.insert(new_fd, duplicated) {
+                    fh.release_pipe(pipe);
+                }
+                new_fd
+            }),
+        };
+        this.try_unwrap_io_result(fd_result)
+    }
+
     fn file_type_to_d_type(&mut self, file_type: std::io::Result<FileType>) -> InterpResult<'tcx, i32> {
         let this = self.eval_context_mut();
         match file_type {
@@ -314,7 +521,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
         let fd = options.open(&path).map(|file| {
             let fh = &mut this.machine.file_handler;
-            fh.insert_fd(FileHandle { file, writable })
+            fh.insert_fd(FileHandle::File { file, writable })
         });
 
         this.try_unwrap_io_result(fd)
@@ -360,27 +567,122 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             })?;
             let start = this.read_scalar(start_op)?.to_i32()?;
             let fh = &mut this.machine.file_handler;
-            let (file_result, writable) = match fh.handles.get(&fd) {
-                Some(FileHandle { file, writable }) => (file.try_clone(), *writable),
+            let file_result = match fh.handles.get(&fd) {
+                Some(file_handle) => file_handle.try_clone(),
                 None => return this.handle_not_found(),
             };
-            let fd_result = file_result.map(|duplicated| {
-                fh.insert_fd_with_min_fd(FileHandle { file: duplicated, writable }, start)
-            });
+            let fd_result = file_result.map(|duplicated| fh.insert_fd_with_min_fd(duplicated, start));
             this.try_unwrap_io_result(fd_result)
         } else {
             throw_unsup_format!("the {:#x} command is not supported for `fcntl`)", cmd);
         }
     }
 
-    fn close(&mut self, fd_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
+    fn dup(&mut self, old_fd_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.check_no_isolation("dup")?;
+
+        let old_fd = this.read_scalar(old_fd_op)?.to_i32()?;
+        this.dup_fd(old_fd, None)
+    }
+
+    fn dup2(
+        &mut self,
+        old_fd_op: OpTy<'tcx, Tag>,
+        new_fd_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.check_no_isolation("dup2")?;
+
+        let old_fd = this.read_scalar(old_fd_op)?.to_i32()?;
+        let new_fd = this.read_scalar(new_fd_op)?.to_i32()?;
+        this.dup_fd(old_fd, Some(new_fd))
+    }
+
+    fn pipe(&mut self, pipefd_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
         let this = self.eval_context_mut();
 
-        this.check_no_isolation("close")?;
+        let pipefd = this.deref_operand(pipefd_op)?;
+
+        let fh = &mut this.machine.file_handler;
+        let pipe = fh.next_pipe;
+        fh.next_pipe += 1;
+        fh.pipes.insert(pipe, VecDeque::new());
+        let read_fd = fh.insert_fd(FileHandle::Pipe { pipe, writable: false });
+        let write_fd = fh.insert_fd(FileHandle::Pipe { pipe, writable: true });
+
+        let int_layout = this.libc_ty_layout("c_int")?;
+        let imms = [
+            immty_from_int_checked(read_fd, int_layout)?,
+            immty_from_int_checked(write_fd, int_layout)?,
+        ];
+        this.write_packed_immediates(pipefd, &imms)?;
+
+        Ok(0)
+    }
+
+    fn pipe2(
+        &mut self,
+        pipefd_op: OpTy<'tcx, Tag>,
+        flags_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.assert_target_os("linux", "pipe2");
+
+        let flags = this.read_scalar(flags_op)?.to_i32()?;
+        // We always assume the FD_CLOEXEC flag is set (see `fcntl`), so `O_CLOEXEC` needs no
+        // handling. There is no non-blocking I/O.
+        let o_cloexec = this.eval_libc_i32("O_CLOEXEC")?;
+        if flags & !o_cloexec != 0 {
+            throw_unsup_format!("`pipe2` flags {:#x} are not supported", flags & !o_cloexec);
+        }
+        this.pipe(pipefd_op)
+    }
+
+    fn dup3(
+        &mut self,
+        old_fd_op: OpTy<'tcx, Tag>,
+        new_fd_op: OpTy<'tcx, Tag>,
+        flags_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.assert_target_os("linux", "dup3");
+        this.check_no_isolation("dup3")?;
+
+        let old_fd = this.read_scalar(old_fd_op)?.to_i32()?;
+        let new_fd = this.read_scalar(new_fd_op)?.to_i32()?;
+        let flags = this.read_scalar(flags_op)?.to_i32()?;
+
+        // We always assume the FD_CLOEXEC flag is set (see `fcntl`), so `O_CLOEXEC` is the only
+        // flag and it needs no handling. Unlike `dup2`, `dup3` rejects equal file descriptors.
+        let o_cloexec = this.eval_libc_i32("O_CLOEXEC")?;
+        if old_fd == new_fd || flags & !o_cloexec != 0 {
+            let einval = this.eval_libc("EINVAL")?;
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+        this.dup_fd(old_fd, Some(new_fd))
+    }
+
+    fn close(&mut self, fd_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        if let Some(FileHandle { file, writable }) = this.machine.file_handler.handles.remove(&fd) {
+        // Pipes only exist inside the interpreter, so they also work with isolation.
+        if !this.machine.file_handler.is_pipe(fd) {
+            this.check_no_isolation("close")?;
+        }
+
+        if let Some(&FileHandle::Pipe { pipe, .. }) = this.machine.file_handler.handles.get(&fd) {
+            this.machine.file_handler.handles.remove(&fd);
+            this.machine.file_handler.release_pipe(pipe);
+            Ok(0)
+        } else if let Some(FileHandle::File { file, writable }) = this.machine.file_handler.handles.remove(&fd) {
             // We sync the file if it was opened in a mode different than read-only.
             if writable {
                 // `File::sync_all` does the checks that are done when closing a file. We do this to
@@ -412,8 +714,6 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
     ) -> InterpResult<'tcx, i64> {
         let this = self.eval_context_mut();
 
-        this.check_no_isolation("read")?;
-
         let fd = this.read_scalar(fd_op)?.to_i32()?;
         let buf = this.read_scalar(buf_op)?.not_undef()?;
         let count = this.read_scalar(count_op)?.to_machine_usize(&*this.tcx)?;
@@ -429,7 +729,31 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         // host's and target's `isize`. This saves us from having to handle overflows later.
         let count = count.min(this.machine_isize_max() as u64).min(isize::MAX as u64);
 
-        if let Some(FileHandle { file, writable: _ }) = this.machine.file_handler.handles.get_mut(&fd) {
+        // Pipes only exist inside the interpreter, so they also work with isolation.
+        if let Some(&FileHandle::Pipe { pipe, writable }) = this.machine.file_handler.handles.get(&fd) {
+            if this.inject_eintr()? {
+                return Ok(-1);
+            }
+            return this.read_from_pipe(pipe, writable, buf, count);
+        }
+        this.check_no_isolation("read")?;
+
+        if this.inject_eintr()? {
+            return Ok(-1);
+        }
+
+        // stdin is not in the file handler table, it is forwarded to the host's stdin.
+        let mut stdin = std::io::stdin();
+        let reader: Option<&mut dyn Read> = if fd == 0 {
+            Some(&mut stdin)
+        } else {
+            match this.machine.file_handler.handles.get_mut(&fd) {
+                Some(FileHandle::File { file, writable: _ }) => Some(file as &mut dyn Read),
+                _ => None,
+            }
+        };
+
+        if let Some(reader) = reader {
             // This can never fail because `count` was capped to be smaller than
             // `isize::MAX`.
             let count = isize::try_from(count).unwrap();
@@ -437,9 +761,9 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             // because it was a target's `usize`. Also we are sure that its smaller than
             // `usize::MAX` because it is a host's `isize`.
             let mut bytes = vec![0; count as usize];
-            let result = file
+            let result = reader
                 .read(&mut bytes)
-                // `File::read` never returns a value larger than `count`, so this cannot fail.
+                // `Read::read` never returns a value larger than `count`, so this cannot fail.
                 .map(|c| i64::try_from(c).unwrap());
 
             match result {
@@ -466,8 +790,6 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
     ) -> InterpResult<'tcx, i64> {
         let this = self.eval_context_mut();
 
-        this.check_no_isolation("write")?;
-
         let fd = this.read_scalar(fd_op)?.to_i32()?;
         let buf = this.read_scalar(buf_op)?.not_undef()?;
         let count = this.read_scalar(count_op)?.to_machine_usize(&*this.tcx)?;
@@ -483,7 +805,20 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         // host's and target's `isize`. This saves us from having to handle overflows later.
         let count = count.min(this.machine_isize_max() as u64).min(isize::MAX as u64);
 
-        if let Some(FileHandle { file, writable: _ }) = this.machine.file_handler.handles.get_mut(&fd) {
+        // Pipes only exist inside the interpreter, so they also work with isolation.
+        if let Some(&FileHandle::Pipe { pipe, writable }) = this.machine.file_handler.handles.get(&fd) {
+            if this.inject_eintr()? {
+                return Ok(-1);
+            }
+            return this.write_to_pipe(pipe, writable, buf, count);
+        }
+        this.check_no_isolation("write")?;
+
+        if this.inject_eintr()? {
+            return Ok(-1);
+        }
+
+        if let Some(FileHandle::File { file, writable: _ }) = this.machine.file_handler.handles.get_mut(&fd) {
             let bytes = this.memory.read_bytes(buf, Size::from_bytes(count))?;
             let result = file.write(&bytes).map(|c| i64::try_from(c).unwrap());
             this.try_unwrap_io_result(result)
@@ -518,7 +853,11 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             return Ok(-1);
         };
 
-        if let Some(FileHandle { file, writable: _ }) = this.machine.file_handler.handles.get_mut(&fd) {
+        if this.machine.file_handler.is_pipe(fd) {
+            let espipe = this.eval_libc("ESPIPE")?;
+            this.set_last_error(espipe)?;
+            Ok(-1)
+        } else if let Some(FileHandle::File { file, writable: _ }) = this.machine.file_handler.handles.get_mut(&fd) {
             let result = file.seek(seek_from).map(|offset| i64::try_from(offset).unwrap());
             this.try_unwrap_io_result(result)
         } else {
@@ -611,6 +950,49 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         this.macos_stat_write_buf(metadata, buf_op)
     }
 
+    fn linux_stat(
+        &mut self,
+        path_op: OpTy<'tcx, Tag>,
+        buf_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "stat");
+        this.check_no_isolation("stat")?;
+        // `stat` always follows symlinks.
+        this.linux_stat_or_lstat(true, path_op, buf_op)
+    }
+
+    // `lstat` is used to get symlink metadata.
+    fn linux_lstat(
+        &mut self,
+        path_op: OpTy<'tcx, Tag>,
+        buf_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "lstat");
+        this.check_no_isolation("lstat")?;
+        this.linux_stat_or_lstat(false, path_op, buf_op)
+    }
+
+    fn linux_fstat(
+        &mut self,
+        fd_op: OpTy<'tcx, Tag>,
+        buf_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.assert_target_os("linux", "fstat");
+        this.check_no_isolation("fstat")?;
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+
+        let metadata = match FileMetadata::from_fd(this, fd)? {
+            Some(metadata) => metadata,
+            None => return Ok(-1),
+        };
+        this.linux_stat_write_buf(metadata, buf_op)
+    }
+
     fn linux_statx(
         &mut self,
         dirfd_op: OpTy<'tcx, Tag>,    // Should be an `int`
@@ -742,12 +1124,12 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             immty_from_uint_checked(mask, __u32_layout)?, // stx_mask
             immty_from_uint_checked(0u128, __u32_layout)?, // stx_blksize
             immty_from_uint_checked(0u128, __u64_layout)?, // stx_attributes
-            immty_from_uint_checked(0u128, __u32_layout)?, // stx_nlink
+            immty_from_uint_checked(metadata.nlink, __u32_layout)?, // stx_nlink
             immty_from_uint_checked(0u128, __u32_layout)?, // stx_uid
             immty_from_uint_checked(0u128, __u32_layout)?, // stx_gid
             immty_from_uint_checked(mode, __u16_layout)?, // stx_mode
             immty_from_uint_checked(0u128, __u16_layout)?, // statx padding
-            immty_from_uint_checked(0u128, __u64_layout)?, // stx_ino
+            immty_from_uint_checked(metadata.ino, __u64_layout)?, // stx_ino
             immty_from_uint_checked(metadata.size, __u64_layout)?, // stx_size
             immty_from_uint_checked(0u128, __u64_layout)?, // stx_blocks
             immty_from_uint_checked(0u128, __u64_layout)?, // stx_attributes
@@ -1078,9 +1460,24 @@ fn extract_sec_and_nsec<'tcx>(
 
 /// Stores a file's metadata in order to avoid code duplication in the different metadata related
 /// shims.
+/// Returns the inode number and the number of hard links of a file, which programs use to check
+/// whether two paths refer to the same file.
+#[cfg(unix)]
+fn ino_and_nlink(metadata: &std::fs::Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.ino(), metadata.nlink())
+}
+#[cfg(not(unix))]
+fn ino_and_nlink(_metadata: &std::fs::Metadata) -> (u64, u64) {
+    // Other hosts do not expose inode numbers; pretend every file has a single link.
+    (0, 1)
+}
+
 struct FileMetadata {
     mode: Scalar<Tag>,
     size: u64,
+    ino: u64,
+    nlink: u64,
     created: Option<(u64, u32)>,
     accessed: Option<(u64, u32)>,
     modified: Option<(u64, u32)>,
@@ -1107,7 +1504,8 @@ impl FileMetadata {
     ) -> InterpResult<'tcx, Option<FileMetadata>> {
         let option = ecx.machine.file_handler.handles.get(&fd);
         let file = match option {
-            Some(FileHandle { file, writable: _ }) => file,
+            Some(FileHandle::File { file, writable: _ }) => file,
+            Some(FileHandle::Pipe { .. }) => throw_unsup_format!("`fstat` is not supported on pipes"),
             None => return ecx.handle_not_found().map(|_: i32| None),
         };
         let metadata = file.metadata();
@@ -1140,12 +1538,13 @@ impl FileMetadata {
         let mode = ecx.eval_libc(mode_name)?;
 
         let size = metadata.len();
+        let (ino, nlink) = ino_and_nlink(&metadata);
 
         let created = extract_sec_and_nsec(metadata.created())?;
         let accessed = extract_sec_and_nsec(metadata.accessed())?;
         let modified = extract_sec_and_nsec(metadata.modified())?;
 
         // FIXME: Provide more fields using platform specific methods.
-        Ok(Some(FileMetadata { mode, size, created, accessed, modified }))
+        Ok(Some(FileMetadata { mode, size, ino, nlink, created, accessed, modified }))
     }
 }
@@ -7,6 +7,8 @@ use rustc_middle::ty::layout::{Align, LayoutOf};
 use rustc_apfloat::Float;
 use rustc_span::source_map::Span;
 
+use rand::Rng;
+
 use crate::*;
 
 impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
@@ -84,8 +86,10 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // be 8-aligned).
                 let align = Align::from_bytes(place.layout.size.bytes()).unwrap();
                 this.memory.check_ptr_access(place.ptr, place.layout.size, align)?;
+                this.check_atomic_access_size(place)?;
 
                 this.write_scalar(val, dest)?;
+                this.record_atomic_access_size(place)?;
             }
 
             #[rustfmt::skip]
@@ -101,8 +105,10 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // be 8-aligned).
                 let align = Align::from_bytes(place.layout.size.bytes()).unwrap();
                 this.memory.check_ptr_access(place.ptr, place.layout.size, align)?;
+                this.check_atomic_access_size(place)?;
 
                 this.write_scalar(val, place.into())?;
+                this.record_atomic_access_size(place)?;
             }
 
             #[rustfmt::skip]
@@ -128,9 +134,11 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // be 8-aligned).
                 let align = Align::from_bytes(place.layout.size.bytes()).unwrap();
                 this.memory.check_ptr_access(place.ptr, place.layout.size, align)?;
+                this.check_atomic_access_size(place)?;
 
                 this.write_scalar(old, dest)?; // old value is returned
                 this.write_scalar(new, place.into())?;
+                this.record_atomic_access_size(place)?;
             }
 
             _ if intrinsic_name.starts_with("atomic_cxchg") => {
@@ -144,9 +152,21 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // be 8-aligned).
                 let align = Align::from_bytes(place.layout.size.bytes()).unwrap();
                 this.memory.check_ptr_access(place.ptr, place.layout.size, align)?;
+                this.check_atomic_access_size(place)?;
 
                 // `binary_op` will bail if either of them is not a scalar.
                 let eq = this.overflowing_binary_op(mir::BinOp::Eq, old, expect_old)?.0;
+                // The weak variants may fail spuriously, which we simulate with the probability
+                // set by `-Zmiri-cxchg-weak-failure-rate`.
+                let rate = this.machine.cxchg_weak_failure_rate;
+                let eq = if intrinsic_name.starts_with("atomic_cxchgweak")
+                    && rate > 0.0
+                    && this.memory.extra.rng.get_mut().gen_bool(rate)
+                {
+                    Scalar::from_bool(false)
+                } else {
+                    eq
+                };
                 let res = Immediate::ScalarPair(old.to_scalar_or_undef(), eq.into());
                 // Return old value.
                 this.write_immediate(res, dest)?;
@@ -154,6 +174,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 if eq.to_bool()? {
                     this.write_scalar(new, place.into())?;
                 }
+                this.record_atomic_access_size(place)?;
             }
 
             #[rustfmt::skip]
@@ -200,6 +221,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // be 8-aligned).
                 let align = Align::from_bytes(place.layout.size.bytes()).unwrap();
                 this.memory.check_ptr_access(place.ptr, place.layout.size, align)?;
+                this.check_atomic_access_size(place)?;
 
                 this.write_immediate(*old, dest)?; // old value is returned
                 let (op, neg) = match intrinsic_name.split('_').nth(1).unwrap() {
@@ -215,6 +237,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let val = this.binary_op(op, old, rhs)?;
                 let val = if neg { this.unary_op(mir::UnOp::Not, val)? } else { val };
                 this.write_immediate(*val, place.into())?;
+                this.record_atomic_access_size(place)?;
             }
 
             "breakpoint" => unimplemented!(), // halt miri
@@ -502,4 +525,40 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         this.go_to_block(ret);
         Ok(())
     }
+
+    /// Checks that an atomic access does not overlap a previous atomic access of a different size.
+    /// Must be called before the access writes to memory, as writes reset the recorded sizes.
+    fn check_atomic_access_size(&self, place: MPlaceTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        let ptr = this.force_ptr(place.ptr)?;
+        let size = place.layout.size;
+        let atomic_access_sizes = this.memory.get_raw(ptr.alloc_id)?.extra.atomic_access_sizes.borrow();
+        if let Some(ref atomic_access_sizes) = *atomic_access_sizes {
+            for &previous_size in atomic_access_sizes.iter(ptr.offset, size) {
+                match previous_size {
+                    Some(previous_size) if previous_size != size => throw_ub_format!(
+                        "mixed-size atomic access: previously accessed as {}-byte atomic, now as {}-byte atomic at the same address",
+                        previous_size.bytes(),
+                        size.bytes(),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the size of an atomic access, for later `check_atomic_access_size` calls.
+    fn record_atomic_access_size(&self, place: MPlaceTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        let ptr = this.force_ptr(place.ptr)?;
+        let alloc = this.memory.get_raw(ptr.alloc_id)?;
+        let mut atomic_access_sizes = alloc.extra.atomic_access_sizes.borrow_mut();
+        let atomic_access_sizes =
+            atomic_access_sizes.get_or_insert_with(|| RangeMap::new(alloc.size, None));
+        for access_size in atomic_access_sizes.iter_mut(ptr.offset, place.layout.size) {
+            *access_size = Some(place.layout.size);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,39 @@
+//! `io_uring` is not supported. Its syscalls fail with `ENOSYS`, as if the kernel were too old to
+//! have it, so that programs can fall back to other ways of doing I/O.
+
+use crate::*;
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Returns whether `id` is the number of one of the `io_uring` syscalls. Versions of `libc`
+    /// that are too old to know these syscalls do not get them.
+    fn is_io_uring_syscall(&mut self, id: u64) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "syscall");
+
+        for &name in &["SYS_io_uring_setup", "SYS_io_uring_enter", "SYS_io_uring_register"] {
+            if let Some(sys) = this.try_eval_libc(name)? {
+                if sys.to_machine_usize(this)? == id {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Emulates the `io_uring` syscall `id` by failing with `ENOSYS`. Returns -1.
+    fn io_uring_syscall(&mut self, id: u64) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "syscall");
+
+        // Only report this once per ring, not for every operation on it.
+        if let Some(sys_setup) = this.try_eval_libc("SYS_io_uring_setup")? {
+            if sys_setup.to_machine_usize(this)? == id {
+                register_diagnostic(NonHaltingDiagnostic::IoUringUnsupported);
+            }
+        }
+        let enosys = this.eval_libc("ENOSYS")?;
+        this.set_last_error(enosys)?;
+        Ok(-1)
+    }
+}
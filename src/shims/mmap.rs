@@ -0,0 +1,166 @@
+//! Emulate anonymous memory mappings.
+
+use std::iter;
+
+use rustc_middle::ty::layout::{Align, Size};
+
+use crate::*;
+
+/// The access permissions of (a part of) an `mmap`ed allocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Protection {
+    pub readable: bool,
+    pub writable: bool,
+}
+
+impl Protection {
+    pub const READ_WRITE: Protection = Protection { readable: true, writable: true };
+}
+
+/// Converts `PROT_*` flags into a `Protection`.
+fn protection_from_flags<'tcx>(
+    this: &mut MiriEvalContext<'_, 'tcx>,
+    prot: i32,
+) -> InterpResult<'tcx, Protection> {
+    let prot_read = this.eval_libc_i32("PROT_READ")?;
+    let prot_write = this.eval_libc_i32("PROT_WRITE")?;
+    let prot_exec = this.eval_libc_i32("PROT_EXEC")?;
+    if prot & !(prot_read | prot_write | prot_exec) != 0 {
+        throw_unsup_format!("unsupported memory protection {:#x}", prot);
+    }
+    // We never execute native code, so `PROT_EXEC` has no effect. Writable memory is also
+    // readable on all the architectures we support.
+    Ok(Protection {
+        readable: prot & (prot_read | prot_write) != 0,
+        writable: prot & prot_write != 0,
+    })
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    fn mmap(
+        &mut self,
+        addr_op: OpTy<'tcx, Tag>,
+        length_op: OpTy<'tcx, Tag>,
+        prot_op: OpTy<'tcx, Tag>,
+        flags_op: OpTy<'tcx, Tag>,
+        fd_op: OpTy<'tcx, Tag>,
+        offset_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, Scalar<Tag>> {
+        let this = self.eval_context_mut();
+
+        // The address is only a hint, which we ignore.
+        let _addr = this.read_scalar(addr_op)?.not_undef()?;
+        let length = this.read_scalar(length_op)?.to_machine_usize(this)?;
+        let prot = this.read_scalar(prot_op)?.to_i32()?;
+        let flags = this.read_scalar(flags_op)?.to_i32()?;
+        // The file descriptor is ignored for anonymous mappings.
+        let _fd = this.read_scalar(fd_op)?.to_i32()?;
+        let offset = this.read_scalar(offset_op)?.to_machine_isize(this)?;
+
+        let map_private = this.eval_libc_i32("MAP_PRIVATE")?;
+        let map_anon = this.eval_libc_i32("MAP_ANON")?;
+        let map_fixed = this.eval_libc_i32("MAP_FIXED")?;
+        if flags & map_fixed != 0 {
+            throw_unsup_format!("`mmap` with `MAP_FIXED` is not supported, Miri does not control allocation addresses");
+        }
+        if flags & map_anon == 0 {
+            throw_unsup_format!("file-backed `mmap` is not supported");
+        }
+        if flags != map_private | map_anon {
+            throw_unsup_format!("unsupported `mmap` flags {:#x}", flags & !(map_private | map_anon));
+        }
+
+        // The length is rounded up to a multiple of the page size.
+        let size = match length.checked_add(PAGE_SIZE - 1) {
+            Some(size) if length != 0 && offset == 0 => size / PAGE_SIZE * PAGE_SIZE,
+            _ => {
+                let einval = this.eval_libc("EINVAL")?;
+                this.set_last_error(einval)?;
+                // This is `MAP_FAILED`.
+                return Ok(Scalar::from_machine_isize(-1, this));
+            }
+        };
+
+        let align = Align::from_bytes(PAGE_SIZE).unwrap();
+        let ptr = this.memory.allocate(Size::from_bytes(size), align, MiriMemoryKind::Mmap.into());
+        // Anonymous mappings are zero-initialized.
+        // We just allocated this, the access is definitely in-bounds.
+        this.memory.write_bytes(ptr.into(), iter::repeat(0u8).take(size as usize)).unwrap();
+
+        let protection = protection_from_flags(this, prot)?;
+        let mmap_protection = this.memory.get_raw(ptr.alloc_id)?.extra.mmap_protection.as_ref().unwrap();
+        for page in mmap_protection.borrow_mut().iter_mut(ptr.offset, Size::from_bytes(size)) {
+            *page = protection;
+        }
+        Ok(Scalar::Ptr(ptr))
+    }
+
+    fn munmap(
+        &mut self,
+        addr_op: OpTy<'tcx, Tag>,
+        length_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_scalar(addr_op)?.not_undef()?;
+        let length = this.read_scalar(length_op)?.to_machine_usize(this)?;
+
+        let ptr = this.force_ptr(addr)?;
+        let size = match length.checked_add(PAGE_SIZE - 1) {
+            Some(size) if length != 0 && ptr.offset.bytes() % PAGE_SIZE == 0 => size / PAGE_SIZE * PAGE_SIZE,
+            _ => {
+                let einval = this.eval_libc("EINVAL")?;
+                this.set_last_error(einval)?;
+                return Ok(-1);
+            }
+        };
+        // We only support unmapping an entire mapping at once.
+        if ptr.offset.bytes() != 0 || this.memory.get_size_and_align(ptr.alloc_id, AllocCheck::Live)?.0.bytes() != size {
+            throw_unsup_format!("`munmap` of only a part of a mapping is not supported");
+        }
+        this.memory.deallocate(ptr, None, MiriMemoryKind::Mmap.into())?;
+        Ok(0)
+    }
+
+    fn mprotect(
+        &mut self,
+        addr_op: OpTy<'tcx, Tag>,
+        length_op: OpTy<'tcx, Tag>,
+        prot_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_scalar(addr_op)?.not_undef()?;
+        let length = this.read_scalar(length_op)?.to_machine_usize(this)?;
+        let prot = this.read_scalar(prot_op)?.to_i32()?;
+
+        let ptr = this.force_ptr(addr)?;
+        // `mmap`ed allocations are page-aligned, so the offset tells us whether `addr` is.
+        let size = match length.checked_add(PAGE_SIZE - 1) {
+            Some(size) if ptr.offset.bytes() % PAGE_SIZE == 0 => size / PAGE_SIZE * PAGE_SIZE,
+            _ => {
+                let einval = this.eval_libc("EINVAL")?;
+                this.set_last_error(einval)?;
+                return Ok(-1);
+            }
+        };
+        let protection = protection_from_flags(this, prot)?;
+
+        let alloc_size = this.memory.get_size_and_align(ptr.alloc_id, AllocCheck::Live)?.0;
+        if ptr.offset.bytes().checked_add(size).map_or(true, |end| end > alloc_size.bytes()) {
+            // Part of the range is not mapped.
+            let enomem = this.eval_libc("ENOMEM")?;
+            this.set_last_error(enomem)?;
+            return Ok(-1);
+        }
+        let mmap_protection = match this.memory.get_raw(ptr.alloc_id)?.extra.mmap_protection {
+            Some(ref mmap_protection) => mmap_protection,
+            None => throw_unsup_format!("`mprotect` is only supported on memory obtained from `mmap`"),
+        };
+        for page in mmap_protection.borrow_mut().iter_mut(ptr.offset, Size::from_bytes(size)) {
+            *page = protection;
+        }
+        Ok(0)
+    }
+}
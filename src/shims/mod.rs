@@ -3,8 +3,13 @@ pub mod env;
 pub mod foreign_items;
 pub mod fs;
 pub mod intrinsics;
+pub mod io_uring;
+pub mod mmap;
+pub mod mqueue;
 pub mod os_str;
 pub mod panic;
+pub mod process;
+pub mod sync;
 pub mod time;
 pub mod tls;
 
@@ -0,0 +1,373 @@
+//! Implement POSIX message queues. Since there is only one process, the queues only live inside
+//! the interpreter and are never visible to the host.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::ty::layout::Size;
+
+use crate::*;
+
+/// The default `mq_maxmsg` of a queue created without attributes, as on Linux.
+const DEFAULT_MAX_MSG: u64 = 10;
+/// The default `mq_msgsize` of a queue created without attributes, as on Linux.
+const DEFAULT_MSG_SIZE: u64 = 8192;
+/// Priorities must be below this value (`MQ_PRIO_MAX` on Linux).
+const MQ_PRIO_MAX: u32 = 32768;
+/// Message queue descriptors are handed out starting from this value, so that they are never
+/// confused with file descriptors.
+const MIN_MQ_DESCRIPTOR: i32 = 1 << 16;
+
+#[derive(Debug)]
+struct MessageQueue {
+    max_msg: u64,
+    msg_size: u64,
+    /// The messages and their priorities, in the order in which they were sent.
+    messages: VecDeque<(u32, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+struct MessageQueueDescriptor {
+    queue: u64,
+    readable: bool,
+    writable: bool,
+    nonblock: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct MessageQueueHandler {
+    queues: BTreeMap<u64, MessageQueue>,
+    /// The queues that can be opened with `mq_open`; unlinked queues are only reachable through
+    /// their descriptors.
+    names: FxHashMap<Vec<u8>, u64>,
+    descriptors: BTreeMap<i32, MessageQueueDescriptor>,
+    next_queue: u64,
+}
+
+impl MessageQueueHandler {
+    fn insert_descriptor(&mut self, descriptor: MessageQueueDescriptor) -> i32 {
+        let mqd = self
+            .descriptors
+            .keys()
+            .zip(MIN_MQ_DESCRIPTOR..)
+            .find(|(mqd, candidate)| *mqd != candidate)
+            .map(|(_, candidate)| candidate)
+            .unwrap_or_else(|| MIN_MQ_DESCRIPTOR + i32::try_from(self.descriptors.len()).unwrap());
+        self.descriptors.insert(mqd, descriptor).unwrap_none();
+        mqd
+    }
+
+    /// Drops the queue once it is neither named nor open.
+    fn remove_if_unused(&mut self, queue: u64) {
+        let named = self.names.values().any(|&q| q == queue);
+        let open = self.descriptors.values().any(|d| d.queue == queue);
+        if !named && !open {
+            self.queues.remove(&queue);
+        }
+    }
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    fn mq_open(
+        &mut self,
+        name_op: OpTy<'tcx, Tag>,
+        flag_op: OpTy<'tcx, Tag>,
+        attr_op: Option<OpTy<'tcx, Tag>>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "mq_open");
+
+        let name = this.memory.read_c_str(this.read_scalar(name_op)?.not_undef()?)?.to_owned();
+        let flag = this.read_scalar(flag_op)?.to_i32()?;
+
+        // Names must consist of a leading slash followed by at least one other character, none
+        // of which may be a slash.
+        if name.len() < 2 || name[0] != b'/' || name[1..].contains(&b'/') {
+            let einval = this.eval_libc("EINVAL")?;
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+
+        let access_mode = flag & 0b11;
+        let (readable, writable) = if access_mode == this.eval_libc_i32("O_RDONLY")? {
+            (true, false)
+        } else if access_mode == this.eval_libc_i32("O_WRONLY")? {
+            (false, true)
+        } else if access_mode == this.eval_libc_i32("O_RDWR")? {
+            (true, true)
+        } else {
+            throw_unsup_format!("unsupported access mode {:#x}", access_mode);
+        };
+        let mut mirror = access_mode;
+
+        let o_nonblock = this.eval_libc_i32("O_NONBLOCK")?;
+        mirror |= flag & o_nonblock;
+        let o_cloexec = this.eval_libc_i32("O_CLOEXEC")?;
+        mirror |= flag & o_cloexec;
+        let o_creat = this.eval_libc_i32("O_CREAT")?;
+        mirror |= flag & o_creat;
+        let o_excl = this.eval_libc_i32("O_EXCL")?;
+        mirror |= flag & o_excl;
+        if flag != mirror {
+            throw_unsup_format!("unsupported flags {:#x}", flag & !mirror);
+        }
+
+        let queue = match this.machine.message_queues.names.get(&name) {
+            Some(_) if flag & o_creat != 0 && flag & o_excl != 0 => {
+                let eexist = this.eval_libc("EEXIST")?;
+                this.set_last_error(eexist)?;
+                return Ok(-1);
+            }
+            Some(&queue) => queue,
+            None if flag & o_creat == 0 => {
+                let enoent = this.eval_libc("ENOENT")?;
+                this.set_last_error(enoent)?;
+                return Ok(-1);
+            }
+            None => {
+                // The permissions are irrelevant, there is only one user.
+                let attr = match attr_op {
+                    Some(attr_op) => this.read_scalar(attr_op)?.not_undef()?,
+                    None => Scalar::null_ptr(this),
+                };
+                let (max_msg, msg_size) = if this.is_null(attr)? {
+                    (DEFAULT_MAX_MSG, DEFAULT_MSG_SIZE)
+                } else {
+                    let attr_place = this.deref_operand(attr_op.unwrap())?;
+                    let max_msg = this.read_scalar(this.mplace_field(attr_place, 1)?.into())?.to_machine_isize(this)?;
+                    let msg_size = this.read_scalar(this.mplace_field(attr_place, 2)?.into())?.to_machine_isize(this)?;
+                    match (u64::try_from(max_msg), u64::try_from(msg_size)) {
+                        (Ok(max_msg), Ok(msg_size)) if max_msg > 0 && msg_size > 0 => (max_msg, msg_size),
+                        _ => {
+                            let einval = this.eval_libc("EINVAL")?;
+                            this.set_last_error(einval)?;
+                            return Ok(-1);
+                        }
+                    }
+                };
+
+                let handler = &mut this.machine.message_queues;
+                let queue = handler.next_queue;
+                handler.next_queue += 1;
+                handler.queues.insert(queue, MessageQueue { max_msg, msg_size, messages: VecDeque::new() });
+                handler.names.insert(name, queue);
+                queue
+            }
+        };
+
+        let descriptor = MessageQueueDescriptor { queue, readable, writable, nonblock: flag & o_nonblock != 0 };
+        Ok(this.machine.message_queues.insert_descriptor(descriptor))
+    }
+
+    fn mq_close(&mut self, mqd_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "mq_close");
+
+        let mqd = this.read_scalar(mqd_op)?.to_i32()?;
+        let handler = &mut this.machine.message_queues;
+        if let Some(descriptor) = handler.descriptors.remove(&mqd) {
+            handler.remove_if_unused(descriptor.queue);
+            Ok(0)
+        } else {
+            this.mq_bad_descriptor()
+        }
+    }
+
+    fn mq_unlink(&mut self, name_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "mq_unlink");
+
+        let name = this.memory.read_c_str(this.read_scalar(name_op)?.not_undef()?)?.to_owned();
+        let handler = &mut this.machine.message_queues;
+        if let Some(queue) = handler.names.remove(&name) {
+            // Open descriptors keep the queue alive.
+            handler.remove_if_unused(queue);
+            Ok(0)
+        } else {
+            let enoent = this.eval_libc("ENOENT")?;
+            this.set_last_error(enoent)?;
+            Ok(-1)
+        }
+    }
+
+    /// Sends a message. `timeout_op` is the timeout argument of `mq_timedsend`.
+    fn mq_send(
+        &mut self,
+        mqd_op: OpTy<'tcx, Tag>,
+        msg_op: OpTy<'tcx, Tag>,
+        len_op: OpTy<'tcx, Tag>,
+        prio_op: OpTy<'tcx, Tag>,
+        timeout_op: Option<OpTy<'tcx, Tag>>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "mq_send");
+
+        let mqd = this.read_scalar(mqd_op)?.to_i32()?;
+        let msg = this.read_scalar(msg_op)?.not_undef()?;
+        let len = this.read_scalar(len_op)?.to_machine_usize(this)?;
+        let prio = this.read_scalar(prio_op)?.to_u32()?;
+
+        let (queue, nonblock) = match this.machine.message_queues.descriptors.get(&mqd) {
+            Some(descriptor) if descriptor.writable => (descriptor.queue, descriptor.nonblock),
+            _ => return this.mq_bad_descriptor(),
+        };
+        let (full, msg_size) = {
+            let queue = &this.machine.message_queues.queues[&queue];
+            (u64::try_from(queue.messages.len()).unwrap() >= queue.max_msg, queue.msg_size)
+        };
+        if len > msg_size {
+            let emsgsize = this.eval_libc("EMSGSIZE")?;
+            this.set_last_error(emsgsize)?;
+            return Ok(-1);
+        }
+        if prio >= MQ_PRIO_MAX {
+            let einval = this.eval_libc("EINVAL")?;
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+        if full {
+            return this.mq_would_block(nonblock, timeout_op);
+        }
+
+        let bytes = this.memory.read_bytes(msg, Size::from_bytes(len))?.to_owned();
+        this.machine.message_queues.queues.get_mut(&queue).unwrap().messages.push_back((prio, bytes));
+        Ok(0)
+    }
+
+    /// Receives the oldest message with the highest priority and returns its length.
+    /// `timeout_op` is the timeout argument of `mq_timedreceive`.
+    fn mq_receive(
+        &mut self,
+        mqd_op: OpTy<'tcx, Tag>,
+        msg_op: OpTy<'tcx, Tag>,
+        len_op: OpTy<'tcx, Tag>,
+        prio_op: OpTy<'tcx, Tag>,
+        timeout_op: Option<OpTy<'tcx, Tag>>,
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "mq_receive");
+
+        let mqd = this.read_scalar(mqd_op)?.to_i32()?;
+        let msg = this.read_scalar(msg_op)?.not_undef()?;
+        let len = this.read_scalar(len_op)?.to_machine_usize(this)?;
+        let prio_ptr = this.read_scalar(prio_op)?.not_undef()?;
+
+        let (queue, nonblock) = match this.machine.message_queues.descriptors.get(&mqd) {
+            Some(descriptor) if descriptor.readable => (descriptor.queue, descriptor.nonblock),
+            _ => return this.mq_bad_descriptor(),
+        };
+        if len < this.machine.message_queues.queues[&queue].msg_size {
+            let emsgsize = this.eval_libc("EMSGSIZE")?;
+            this.set_last_error(emsgsize)?;
+            return Ok(-1);
+        }
+
+        let messages = &mut this.machine.message_queues.queues.get_mut(&queue).unwrap().messages;
+        // `max_by_key` returns the last maximum, but we want the oldest message.
+        let idx = messages.iter().enumerate().rev().max_by_key(|(_, (prio, _))| *prio).map(|(idx, _)| idx);
+        let (prio, bytes) = match idx {
+            Some(idx) => messages.remove(idx).unwrap(),
+            None => return Ok(this.mq_would_block(nonblock, timeout_op)?.into()),
+        };
+
+        this.memory.write_bytes(msg, bytes.iter().copied())?;
+        if !this.is_null(prio_ptr)? {
+            let prio_place = this.deref_operand(prio_op)?;
+            this.write_scalar(Scalar::from_u32(prio), prio_place.into())?;
+        }
+        Ok(i64::try_from(bytes.len()).unwrap())
+    }
+
+    /// Writes the attributes of the queue to `old_attr_op` (if it is not null), and then sets the
+    /// `O_NONBLOCK` flag from `new_attr_op` (if given). This implements `mq_getattr` and
+    /// `mq_setattr`.
+    fn mq_getsetattr(
+        &mut self,
+        mqd_op: OpTy<'tcx, Tag>,
+        new_attr_op: Option<OpTy<'tcx, Tag>>,
+        old_attr_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("linux", "mq_getsetattr");
+
+        let mqd = this.read_scalar(mqd_op)?.to_i32()?;
+        let o_nonblock = this.eval_libc_i32("O_NONBLOCK")?;
+        let (queue, nonblock) = match this.machine.message_queues.descriptors.get(&mqd) {
+            Some(descriptor) => (descriptor.queue, descriptor.nonblock),
+            None => return this.mq_bad_descriptor(),
+        };
+
+        let new_flags = match new_attr_op {
+            Some(new_attr_op) => {
+                let new_attr_place = this.deref_operand(new_attr_op)?;
+                let flags = this.read_scalar(this.mplace_field(new_attr_place, 0)?.into())?.to_machine_isize(this)?;
+                // All other attributes are ignored.
+                if flags & !i64::from(o_nonblock) != 0 {
+                    let einval = this.eval_libc("EINVAL")?;
+                    this.set_last_error(einval)?;
+                    return Ok(-1);
+                }
+                Some(flags)
+            }
+            None => None,
+        };
+
+        if !this.is_null(this.read_scalar(old_attr_op)?.not_undef()?)? {
+            let (max_msg, msg_size, cur_msgs) = {
+                let queue = &this.machine.message_queues.queues[&queue];
+                (queue.max_msg, queue.msg_size, u64::try_from(queue.messages.len()).unwrap())
+            };
+            let flags = if nonblock { u64::try_from(o_nonblock).unwrap() } else { 0 };
+            let old_attr_place = this.deref_operand(old_attr_op)?;
+            // `mq_flags`, `mq_maxmsg`, `mq_msgsize` and `mq_curmsgs`; the rest is reserved.
+            let values = [flags, max_msg, msg_size, cur_msgs];
+            for (idx, &value) in values.iter().enumerate() {
+                let field = this.mplace_field(old_attr_place, u64::try_from(idx).unwrap())?;
+                this.write_scalar(Scalar::from_machine_usize(value, this), field.into())?;
+            }
+        }
+
+        if let Some(flags) = new_flags {
+            this.machine.message_queues.descriptors.get_mut(&mqd).unwrap().nonblock = flags != 0;
+        }
+        Ok(0)
+    }
+}
+
+impl<'mir, 'tcx> EvalContextExtPrivate<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+trait EvalContextExtPrivate<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Fails with `EBADF`, for descriptors that are not open (for the requested operation).
+    fn mq_bad_descriptor<T: From<i32>>(&mut self) -> InterpResult<'tcx, T> {
+        let this = self.eval_context_mut();
+        let ebadf = this.eval_libc("EBADF")?;
+        this.set_last_error(ebadf)?;
+        Ok((-1).into())
+    }
+
+    /// Handles a send to a full queue or a receive from an empty queue. There is no other thread
+    /// that could ever change the queue, so blocking forever deadlocks and any timeout expires.
+    fn mq_would_block(
+        &mut self,
+        nonblock: bool,
+        timeout_op: Option<OpTy<'tcx, Tag>>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let errno = if nonblock {
+            "EAGAIN"
+        } else if let Some(timeout_op) = timeout_op {
+            if this.read_timespec(this.deref_operand(timeout_op)?)?.is_some() {
+                "ETIMEDOUT"
+            } else {
+                "EINVAL"
+            }
+        } else {
+            throw_machine_stop!(TerminationInfo::Deadlock)
+        };
+        let errno = this.eval_libc(errno)?;
+        this.set_last_error(errno)?;
+        Ok(-1)
+    }
+}
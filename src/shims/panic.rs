@@ -127,6 +127,17 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         if let Some(stacked_borrows) = this.memory.extra.stacked_borrows.as_ref() {
             stacked_borrows.borrow_mut().end_call(extra.call_id);
         }
+        // Jumping into this frame is no longer possible.
+        if let Some(targets) = this.machine.jmp_targets.remove(&extra.frame_id) {
+            for target in targets {
+                this.machine.jmp_tokens.remove(&target.token);
+            }
+        }
+
+        if let (false, Some(once)) = (unwinding, extra.once_completion.take()) {
+            // The initialization function of a once object returned.
+            this.complete_once(once)?;
+        }
 
         // We only care about `catch_panic` if we're unwinding - if we're doing a normal
         // return, then we don't need to do anything special.
@@ -0,0 +1,206 @@
+use std::convert::{TryFrom, TryInto};
+
+use log::trace;
+
+use rustc_middle::mir;
+use rustc_middle::ty::layout::Size;
+use rustc_target::abi::LayoutOf;
+
+use crate::*;
+
+/// The maximum number of handlers that can be registered with `atexit`.
+/// POSIX requires this to be at least 32.
+const ATEXIT_MAX: usize = 32;
+
+/// The tokens that `setjmp` stores in a `jmp_buf` start from this value, so that a `jmp_buf`
+/// that was zero-initialized instead is never mistaken for a valid one.
+pub const MIN_JMP_TOKEN: u64 = 0x6d69_7269_0000_0000;
+
+/// A `setjmp` call that `longjmp` can return to.
+#[derive(Debug)]
+pub struct JmpTarget<'tcx> {
+    /// The token that the `jmp_buf` holds.
+    pub token: u64,
+    /// The return place and block of the `setjmp` call.
+    pub dest: PlaceTy<'tcx, Tag>,
+    pub ret: mir::BasicBlock,
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    fn getpid(&self) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_ref();
+        let target_os = &this.tcx.sess.target.target.target_os;
+        assert!(target_os == "linux" || target_os == "macos", "`getpid` is only available for the UNIX target family");
+
+        let pid = if this.machine.communicate {
+            std::process::id()
+        } else {
+            // Use a fixed fake PID so that isolated executions are deterministic.
+            this.machine.fake_pid
+        };
+        i32::try_from(pid).map_err(|_| err_unsup_format!("process id {} does not fit into a `pid_t`", pid).into())
+    }
+
+    fn getppid(&self) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_ref();
+        let target_os = &this.tcx.sess.target.target.target_os;
+        assert!(target_os == "linux" || target_os == "macos", "`getppid` is only available for the UNIX target family");
+
+        let ppid = if this.machine.communicate {
+            #[cfg(unix)]
+            {
+                std::os::unix::process::parent_id()
+            }
+            #[cfg(not(unix))]
+            throw_unsup_format!("`getppid` is only supported on Unix hosts when isolation is disabled")
+        } else {
+            // The fake parent is the "process" that was started right before us.
+            this.machine.fake_pid - 1
+        };
+        i32::try_from(ppid).map_err(|_| err_unsup_format!("process id {} does not fit into a `pid_t`", ppid).into())
+    }
+
+    /// Returns the id of the current thread. There is only one thread, and the kernel gives the
+    /// main thread the same id as the process.
+    fn linux_gettid(&self) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_ref();
+        this.assert_target_os("linux", "gettid");
+
+        this.getpid()
+    }
+
+    /// Registers `func` to be called when the program terminates normally. For `__cxa_atexit`,
+    /// `arg` is the argument that `func` gets called with. Returns 0 on success.
+    fn register_atexit_handler(
+        &mut self,
+        func: Scalar<Tag>,
+        arg: Option<Scalar<Tag>>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let func = this.memory.get_fn(func)?.as_instance()?;
+        if this.machine.atexit_handlers.len() >= ATEXIT_MAX {
+            return Ok(-1);
+        }
+        this.machine.atexit_handlers.push((func, arg));
+        Ok(0)
+    }
+
+    /// Registers `func` to be called by `quick_exit`. Returns 0 on success.
+    fn register_quick_exit_handler(&mut self, func: Scalar<Tag>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let func = this.memory.get_fn(func)?.as_instance()?;
+        // C requires at least 32 handlers to be supported here as well.
+        if this.machine.quick_exit_handlers.len() >= ATEXIT_MAX {
+            return Ok(-1);
+        }
+        this.machine.quick_exit_handlers.push(func);
+        Ok(0)
+    }
+
+    /// Runs all handlers registered with `atexit` (or with `at_quick_exit`, if `quick_exit` is
+    /// set), in the reverse order of their registration. Handlers that get registered while this
+    /// is running are run as well.
+    fn run_atexit_handlers(&mut self, quick_exit: bool) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        loop {
+            let (instance, arg) = if quick_exit {
+                match this.machine.quick_exit_handlers.pop() {
+                    Some(instance) => (instance, None),
+                    None => break,
+                }
+            } else {
+                match this.machine.atexit_handlers.pop() {
+                    Some(handler) => handler,
+                    None => break,
+                }
+            };
+            trace!("Running exit handler {:?} with {:?}", instance, arg);
+
+            let args: Vec<Immediate<Tag>> = arg.into_iter().map(Immediate::from).collect();
+            let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+            // When the program called `exit`, the frames of its callers are still on the stack,
+            // so we only step until the handler returns.
+            let depth = this.stack().len();
+            this.call_function(
+                instance,
+                &args,
+                Some(ret_place),
+                StackPopCleanup::None { cleanup: true },
+            )?;
+            while this.stack().len() > depth {
+                this.step_with_bookkeeping()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the current frame and the return point of this `setjmp` call, and stores a token
+    /// identifying them in the `jmp_buf`. Returns 0, like the first return of `setjmp`.
+    fn setjmp(
+        &mut self,
+        env_op: OpTy<'tcx, Tag>,
+        dest: PlaceTy<'tcx, Tag>,
+        ret: mir::BasicBlock,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let env = this.read_scalar(env_op)?.not_undef()?;
+        let frame_id = this.frame().extra.frame_id;
+        let targets = this.machine.jmp_targets.entry(frame_id).or_default();
+        // Calling `setjmp` at the same place again (e.g. in a loop) reuses the token, so that the
+        // number of targets stays bounded.
+        let token = match targets.iter().find(|target| target.ret == ret) {
+            Some(target) => target.token,
+            None => {
+                let token = this.machine.next_jmp_token;
+                this.machine.next_jmp_token += 1;
+                this.machine.jmp_tokens.insert(token, (frame_id, targets.len()));
+                targets.push(JmpTarget { token, dest, ret });
+                token
+            }
+        };
+        this.memory.write_bytes(env, token.to_le_bytes().iter().copied())?;
+        Ok(0)
+    }
+
+    /// Makes the `setjmp` call that initialized the `jmp_buf` return again, this time with `val`.
+    /// We only support jumping within the same frame: every frame we execute is a Rust frame (the
+    /// interpreter never runs C code), so jumping out of a frame would skip its destructors, and
+    /// the "pure C" case in which such a jump would be allowed cannot occur.
+    fn longjmp(&mut self, env_op: OpTy<'tcx, Tag>, val_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let env = this.read_scalar(env_op)?.not_undef()?;
+        let val = this.read_scalar(val_op)?.to_i32()?;
+        let token = this.memory.read_bytes(env, Size::from_bytes(8))?;
+        let token = u64::from_le_bytes(token.try_into().unwrap());
+
+        let (frame_id, idx) = match this.machine.jmp_tokens.get(&token) {
+            Some(&target) => target,
+            // The tokens of a frame are removed when it returns.
+            None if (MIN_JMP_TOKEN..this.machine.next_jmp_token).contains(&token) => {
+                throw_ub_format!("`longjmp` into a function that has already returned")
+            }
+            None => {
+                throw_ub_format!("`longjmp` called with a `jmp_buf` that was not initialized by `setjmp`")
+            }
+        };
+        let target = &this.machine.jmp_targets[&frame_id][idx];
+        let (dest, ret) = (target.dest, target.ret);
+        // The frame that called `setjmp` is still live, so if it is not the current frame, it is
+        // further up the stack.
+        if this.frame().extra.frame_id != frame_id {
+            throw_ub_format!("longjmp across Rust stack frames with live drop obligations");
+        }
+
+        // `setjmp` returns `val` the second time, except that 0 is turned into 1.
+        let val = if val == 0 { 1 } else { val };
+        this.write_scalar(Scalar::from_i32(val), dest)?;
+        this.go_to_block(ret);
+        Ok(())
+    }
+}
@@ -0,0 +1,666 @@
+use std::collections::BTreeMap;
+
+use log::trace;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir;
+use rustc_middle::ty::layout::{Align, Size};
+use rustc_target::abi::LayoutOf;
+
+use crate::*;
+
+// Miri is single-threaded, so a lock can only ever be held by the current thread. Trying to
+// acquire a lock that is already held therefore can never succeed.
+
+/// The value of an `os_unfair_lock` that is not held (`OS_UNFAIR_LOCK_INIT`).
+const UNFAIR_LOCK_UNLOCKED: u32 = 0;
+/// The value we store in an `os_unfair_lock` that is held by the (only) thread.
+const UNFAIR_LOCK_LOCKED: u32 = 1;
+
+// Once objects (`dispatch_once_t`, `INIT_ONCE`) are pointer-sized and statically initialized to 0.
+
+/// The value of a once object whose initialization function has not run yet.
+const ONCE_INIT: i64 = 0;
+/// The value we store in a once object while its initialization function is running.
+const ONCE_IN_PROGRESS: i64 = 1;
+/// The value we store in a once object whose initialization has completed. This matches
+/// `DISPATCH_ONCE_DONE`, which the inline fast path of `dispatch_once` checks for.
+const ONCE_DONE: i64 = !0;
+
+/// `INIT_ONCE_CHECK_ONLY`: only check whether the initialization has completed.
+const INIT_ONCE_CHECK_ONLY: u32 = 1;
+/// `INIT_ONCE_INIT_FAILED`: the initialization failed and may be retried.
+const INIT_ONCE_INIT_FAILED: u32 = 4;
+/// The error code for `INIT_ONCE` operations that are invalid in the current state.
+const ERROR_GEN_FAILURE: u32 = 31;
+
+/// What to do when the initialization function of a once object returns.
+#[derive(Debug)]
+pub struct OnceCompletion<'tcx> {
+    /// The once object.
+    pub once: MPlaceTy<'tcx, Tag>,
+    /// If set, the initialization function returned a `BOOL` into this place, and the once
+    /// object is only done if that is not `FALSE`.
+    pub status: Option<PlaceTy<'tcx, Tag>>,
+}
+
+/// `DISPATCH_TIME_FOREVER`: wait without a timeout.
+const DISPATCH_TIME_FOREVER: u64 = !0;
+
+// A `dispatch_semaphore_t` points to a `C` allocation holding the current value of the semaphore
+// as an `i64` at offset 0, followed by the reference count of the object as an `i64`.
+const DISPATCH_SEMAPHORE_SIZE: u64 = 16;
+const DISPATCH_SEMAPHORE_VALUE_OFFSET: u64 = 0;
+const DISPATCH_SEMAPHORE_REFCOUNT_OFFSET: u64 = 8;
+
+/// `WAIT_OBJECT_0`: the object that was waited for has been acquired.
+const WAIT_OBJECT_0: u32 = 0;
+/// The error code of `CreateMutexW` when a mutex with the given name already exists.
+const ERROR_ALREADY_EXISTS: u32 = 183;
+/// The error code of `OpenMutexW` when there is no mutex with the given name.
+const ERROR_FILE_NOT_FOUND: u32 = 2;
+/// The error code of `ReleaseMutex` when the mutex is not held.
+const ERROR_NOT_OWNER: u32 = 288;
+/// Mutex handles are handed out starting from this value, so that they are never confused with
+/// the pseudo-handles of `GetStdHandle` and `GetProcessHeap`.
+const MIN_MUTEX_HANDLE: i64 = 1 << 16;
+
+#[derive(Debug)]
+struct WindowsMutex {
+    name: Option<Vec<u16>>,
+    /// How often the mutex has been acquired without being released. Windows mutexes are
+    /// recursive, and since there is only one thread, a mutex with a non-zero count is owned by
+    /// the current thread.
+    lock_count: u32,
+}
+
+/// The Windows mutex objects, and the handles that refer to them.
+#[derive(Debug, Default)]
+pub struct WindowsMutexHandler {
+    mutexes: BTreeMap<u64, WindowsMutex>,
+    names: FxHashMap<Vec<u16>, u64>,
+    handles: BTreeMap<i64, u64>,
+    next_mutex: u64,
+    next_handle: i64,
+}
+
+impl WindowsMutexHandler {
+    fn insert_handle(&mut self, mutex: u64) -> i64 {
+        let handle = MIN_MUTEX_HANDLE + self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(handle, mutex).unwrap_none();
+        handle
+    }
+
+    fn mutex_mut<'tcx>(&mut self, handle: i64, link_name: &str) -> InterpResult<'tcx, &mut WindowsMutex> {
+        match self.handles.get(&handle) {
+            Some(mutex) => Ok(self.mutexes.get_mut(mutex).unwrap()),
+            None => throw_unsup_format!("`{}` is only supported on mutex handles", link_name),
+        }
+    }
+
+    /// Drops the mutex once the last handle to it is closed.
+    fn remove_if_unused(&mut self, mutex: u64) {
+        if !self.handles.values().any(|&m| m == mutex) {
+            let mutex = self.mutexes.remove(&mutex).unwrap();
+            if let Some(name) = mutex.name {
+                self.names.remove(&name);
+            }
+        }
+    }
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Returns the `uint32_t` that makes up an `os_unfair_lock`.
+    fn unfair_lock_place(&self, lock_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, MPlaceTy<'tcx, Tag>> {
+        let this = self.eval_context_ref();
+        let lock = this.deref_operand(lock_op)?;
+        let u32_layout = this.layout_of(this.tcx.types.u32)?;
+        lock.offset(Size::ZERO, MemPlaceMeta::None, u32_layout, &*this.tcx)
+    }
+
+    /// Returns whether the `os_unfair_lock` is held, rejecting values we never store.
+    fn unfair_lock_is_locked(&self, lock: MPlaceTy<'tcx, Tag>) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_ref();
+        match this.read_scalar(lock.into())?.to_u32()? {
+            UNFAIR_LOCK_UNLOCKED => Ok(false),
+            UNFAIR_LOCK_LOCKED => Ok(true),
+            _ => throw_ub_format!("`os_unfair_lock` is not initialized to `OS_UNFAIR_LOCK_INIT`"),
+        }
+    }
+
+    fn os_unfair_lock_lock(&mut self, lock_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "os_unfair_lock_lock");
+
+        let lock = this.unfair_lock_place(lock_op)?;
+        if this.unfair_lock_is_locked(lock)? {
+            throw_ub_format!("trying to recursively lock an `os_unfair_lock` that is already held by the current thread");
+        }
+        this.write_scalar(Scalar::from_u32(UNFAIR_LOCK_LOCKED), lock.into())
+    }
+
+    /// Returns whether the lock was acquired.
+    fn os_unfair_lock_trylock(&mut self, lock_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "os_unfair_lock_trylock");
+
+        let lock = this.unfair_lock_place(lock_op)?;
+        if this.unfair_lock_is_locked(lock)? {
+            return Ok(false);
+        }
+        this.write_scalar(Scalar::from_u32(UNFAIR_LOCK_LOCKED), lock.into())?;
+        Ok(true)
+    }
+
+    fn os_unfair_lock_unlock(&mut self, lock_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "os_unfair_lock_unlock");
+
+        let lock = this.unfair_lock_place(lock_op)?;
+        if !this.unfair_lock_is_locked(lock)? {
+            throw_ub_format!("unlocking an `os_unfair_lock` that is not held by the current thread");
+        }
+        this.write_scalar(Scalar::from_u32(UNFAIR_LOCK_UNLOCKED), lock.into())
+    }
+
+    fn os_unfair_lock_assert_owner(&self, lock_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        this.assert_target_os("macos", "os_unfair_lock_assert_owner");
+
+        let lock = this.unfair_lock_place(lock_op)?;
+        if !this.unfair_lock_is_locked(lock)? {
+            throw_ub_format!("`os_unfair_lock_assert_owner` failed: the lock is not held by the current thread");
+        }
+        Ok(())
+    }
+
+    fn os_unfair_lock_assert_not_owner(&self, lock_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        this.assert_target_os("macos", "os_unfair_lock_assert_not_owner");
+
+        let lock = this.unfair_lock_place(lock_op)?;
+        if this.unfair_lock_is_locked(lock)? {
+            throw_ub_format!("`os_unfair_lock_assert_not_owner` failed: the lock is held by the current thread");
+        }
+        Ok(())
+    }
+
+    /// Returns the field at `offset` of the `dispatch_semaphore_t` that `sema_op` points to.
+    fn dispatch_semaphore_field(
+        &self,
+        sema_op: OpTy<'tcx, Tag>,
+        offset: u64,
+    ) -> InterpResult<'tcx, MPlaceTy<'tcx, Tag>> {
+        let this = self.eval_context_ref();
+        let sema = this.read_scalar(sema_op)?.not_undef()?;
+        let sema = this.force_ptr(sema)?;
+        let i64_layout = this.layout_of(this.tcx.types.i64)?;
+        Ok(MPlaceTy::from_aligned_ptr(sema.offset(Size::from_bytes(offset), this)?, i64_layout))
+    }
+
+    fn dispatch_semaphore_create(&mut self, value_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, Scalar<Tag>> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "dispatch_semaphore_create");
+
+        let value = this.read_scalar(value_op)?.to_machine_isize(this)?;
+        if value < 0 {
+            // Creating a semaphore with a negative value fails.
+            return Ok(Scalar::null_ptr(this));
+        }
+
+        let sema = this.memory.allocate(
+            Size::from_bytes(DISPATCH_SEMAPHORE_SIZE),
+            Align::from_bytes(8).unwrap(),
+            MiriMemoryKind::Dispatch.into(),
+        );
+        let i64_layout = this.layout_of(this.tcx.types.i64)?;
+        let fields = [(DISPATCH_SEMAPHORE_VALUE_OFFSET, value), (DISPATCH_SEMAPHORE_REFCOUNT_OFFSET, 1)];
+        for &(offset, field) in fields.iter() {
+            let place = MPlaceTy::from_aligned_ptr(sema.offset(Size::from_bytes(offset), this)?, i64_layout);
+            this.write_scalar(Scalar::from_i64(field), place.into())?;
+        }
+        Ok(sema.into())
+    }
+
+    /// Returns 0 on success and non-zero if the timeout expired.
+    fn dispatch_semaphore_wait(
+        &mut self,
+        sema_op: OpTy<'tcx, Tag>,
+        timeout_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "dispatch_semaphore_wait");
+
+        let value_place = this.dispatch_semaphore_field(sema_op, DISPATCH_SEMAPHORE_VALUE_OFFSET)?;
+        let timeout = this.read_scalar(timeout_op)?.to_u64()?;
+        let value = this.read_scalar(value_place.into())?.to_i64()?;
+        if value > 0 {
+            this.write_scalar(Scalar::from_i64(value - 1), value_place.into())?;
+            return Ok(0);
+        }
+        match timeout {
+            // There is no other thread that could ever signal the semaphore.
+            DISPATCH_TIME_FOREVER => throw_machine_stop!(TerminationInfo::Deadlock),
+            // For the same reason, any other timeout (including `DISPATCH_TIME_NOW`) is going to
+            // expire; we do not bother to actually wait for it.
+            _ => Ok(1),
+        }
+    }
+
+    /// Returns non-zero if a waiting thread was woken up, which can never happen for us.
+    fn dispatch_semaphore_signal(&mut self, sema_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "dispatch_semaphore_signal");
+
+        let value_place = this.dispatch_semaphore_field(sema_op, DISPATCH_SEMAPHORE_VALUE_OFFSET)?;
+        let value = this.read_scalar(value_place.into())?.to_i64()?;
+        let value = value.checked_add(1).ok_or_else(|| err_unsup_format!("`dispatch_semaphore_t` value overflow"))?;
+        this.write_scalar(Scalar::from_i64(value), value_place.into())?;
+        Ok(0)
+    }
+
+    /// `dispatch_retain`; the only dispatch objects we support are semaphores.
+    fn dispatch_retain(&mut self, object_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "dispatch_retain");
+
+        let refcount_place = this.dispatch_semaphore_field(object_op, DISPATCH_SEMAPHORE_REFCOUNT_OFFSET)?;
+        let refcount = this.read_scalar(refcount_place.into())?.to_i64()?;
+        this.write_scalar(Scalar::from_i64(refcount + 1), refcount_place.into())
+    }
+
+    /// `dispatch_release`; frees the semaphore once the last reference is gone.
+    fn dispatch_release(&mut self, object_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "dispatch_release");
+
+        let refcount_place = this.dispatch_semaphore_field(object_op, DISPATCH_SEMAPHORE_REFCOUNT_OFFSET)?;
+        let refcount = this.read_scalar(refcount_place.into())?.to_i64()?;
+        if refcount > 1 {
+            return this.write_scalar(Scalar::from_i64(refcount - 1), refcount_place.into());
+        }
+        // This was the last reference.
+        let sema = this.read_scalar(object_op)?.not_undef()?;
+        this.memory.deallocate(this.force_ptr(sema)?, None, MiriMemoryKind::Dispatch.into())
+    }
+
+    /// Returns the once object that `once_op` points to, and its current state.
+    fn once_place(&self, once_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx, (MPlaceTy<'tcx, Tag>, i64)> {
+        let this = self.eval_context_ref();
+        let once = this.read_scalar(once_op)?.not_undef()?;
+        let isize_layout = this.layout_of(this.tcx.types.isize)?;
+        let once = MPlaceTy::from_aligned_ptr(this.force_ptr(once)?, isize_layout);
+        let state = this.read_scalar(once.into())?.to_machine_isize(this)?;
+        if state != ONCE_INIT && state != ONCE_IN_PROGRESS && state != ONCE_DONE {
+            throw_ub_format!("once object is not statically initialized to 0");
+        }
+        Ok((once, state))
+    }
+
+    fn set_once_state(&mut self, once: MPlaceTy<'tcx, Tag>, state: i64) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.write_scalar(Scalar::from_machine_isize(state, this), once.into())
+    }
+
+    /// Runs `function(ctx)` unless `dispatch_once_f` has already been called with `pred`. Returns
+    /// `true` if the caller should jump to the return block, and `false` if we pushed the frame of
+    /// the initialization function (which returns to `ret` directly).
+    fn dispatch_once_f(
+        &mut self,
+        pred_op: OpTy<'tcx, Tag>,
+        ctx_op: OpTy<'tcx, Tag>,
+        function_op: OpTy<'tcx, Tag>,
+        ret: mir::BasicBlock,
+    ) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("macos", "dispatch_once_f");
+
+        let (pred, state) = this.once_place(pred_op)?;
+        match state {
+            ONCE_DONE => return Ok(true),
+            // The initialization function (transitively) called `dispatch_once_f` on its own
+            // predicate, so it waits for itself.
+            ONCE_IN_PROGRESS => throw_machine_stop!(TerminationInfo::Deadlock),
+            _ => {}
+        }
+
+        let ctx = this.read_scalar(ctx_op)?.not_undef()?;
+        let function = this.read_scalar(function_op)?.not_undef()?;
+        let function = this.memory.get_fn(function)?.as_instance()?;
+        trace!("dispatch_once_f: running {:?} with {:?}", function, ctx);
+
+        this.set_once_state(pred, ONCE_IN_PROGRESS)?;
+        let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+        this.call_function(
+            function,
+            &[ctx.into()],
+            Some(ret_place),
+            // Directly return to caller.
+            StackPopCleanup::Goto { ret: Some(ret), unwind: None },
+        )?;
+        // `handle_stack_pop` marks the predicate as done once this frame returns.
+        this.frame_mut().extra.once_completion = Some(OnceCompletion { once: pred, status: None });
+        Ok(false)
+    }
+
+    /// Runs `InitFn(InitOnce, Parameter, Context)` unless the `INIT_ONCE` has already been
+    /// initialized. `InitFn` returns its `BOOL` directly into `dest`. Returns `true` if the caller
+    /// should jump to the return block, and `false` if we pushed the frame of `InitFn`.
+    #[allow(non_snake_case)]
+    fn InitOnceExecuteOnce(
+        &mut self,
+        init_once_op: OpTy<'tcx, Tag>, // PINIT_ONCE
+        init_fn_op: OpTy<'tcx, Tag>, // PINIT_ONCE_FN
+        parameter_op: OpTy<'tcx, Tag>, // PVOID
+        context_op: OpTy<'tcx, Tag>, // LPVOID *
+        dest: PlaceTy<'tcx, Tag>, // BOOL
+        ret: mir::BasicBlock,
+    ) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "InitOnceExecuteOnce");
+
+        let init_once_ptr = this.read_scalar(init_once_op)?.not_undef()?;
+        let context = this.read_scalar(context_op)?.not_undef()?;
+        let (init_once, state) = this.once_place(init_once_op)?;
+        match state {
+            ONCE_DONE => {
+                if !this.is_null(context)? {
+                    throw_unsup_format!("retrieving the context of an initialized `INIT_ONCE` is not supported");
+                }
+                this.write_scalar(Scalar::from_i32(1), dest)?;
+                return Ok(true);
+            }
+            // `InitFn` (transitively) tried to initialize its own `INIT_ONCE`, so it waits for itself.
+            ONCE_IN_PROGRESS => throw_machine_stop!(TerminationInfo::Deadlock),
+            _ => {}
+        }
+
+        let parameter = this.read_scalar(parameter_op)?.not_undef()?;
+        let init_fn = this.read_scalar(init_fn_op)?.not_undef()?;
+        let init_fn = this.memory.get_fn(init_fn)?.as_instance()?;
+        trace!("InitOnceExecuteOnce: running {:?} with {:?}", init_fn, parameter);
+
+        this.set_once_state(init_once, ONCE_IN_PROGRESS)?;
+        this.call_function(
+            init_fn,
+            &[init_once_ptr.into(), parameter.into(), context.into()],
+            Some(dest),
+            // Directly return to caller.
+            StackPopCleanup::Goto { ret: Some(ret), unwind: None },
+        )?;
+        // `handle_stack_pop` marks the `INIT_ONCE` as done if `InitFn` succeeds, and resets it so
+        // that initialization can be retried if `InitFn` fails.
+        this.frame_mut().extra.once_completion = Some(OnceCompletion { once: init_once, status: Some(dest) });
+        Ok(false)
+    }
+
+    /// The first half of synchronous one-time initialization. Returns `TRUE` on success, and sets
+    /// `*fPending` to whether the caller has to perform the initialization.
+    #[allow(non_snake_case)]
+    fn InitOnceBeginInitialize(
+        &mut self,
+        init_once_op: OpTy<'tcx, Tag>, // LPINIT_ONCE
+        flags_op: OpTy<'tcx, Tag>, // DWORD
+        pending_op: OpTy<'tcx, Tag>, // PBOOL
+        context_op: OpTy<'tcx, Tag>, // LPVOID *
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "InitOnceBeginInitialize");
+
+        let (init_once, state) = this.once_place(init_once_op)?;
+        let flags = this.read_scalar(flags_op)?.to_u32()?;
+        let pending = this.deref_operand(pending_op)?;
+        let context = this.read_scalar(context_op)?.not_undef()?;
+        if flags & !INIT_ONCE_CHECK_ONLY != 0 {
+            throw_unsup_format!("unsupported `dwFlags` value for `InitOnceBeginInitialize`: {:#x}", flags);
+        }
+
+        match state {
+            ONCE_DONE => {
+                if !this.is_null(context)? {
+                    throw_unsup_format!("retrieving the context of an initialized `INIT_ONCE` is not supported");
+                }
+                this.write_scalar(Scalar::from_i32(0), pending.into())?;
+                Ok(1)
+            }
+            _ if flags & INIT_ONCE_CHECK_ONLY != 0 => {
+                // Initialization has not completed yet.
+                this.set_last_error(Scalar::from_u32(ERROR_GEN_FAILURE))?;
+                Ok(0)
+            }
+            // Somebody already began the initialization, and nobody but us could complete it.
+            ONCE_IN_PROGRESS => throw_machine_stop!(TerminationInfo::Deadlock),
+            _ => {
+                this.set_once_state(init_once, ONCE_IN_PROGRESS)?;
+                this.write_scalar(Scalar::from_i32(1), pending.into())?;
+                Ok(1)
+            }
+        }
+    }
+
+    /// The second half of synchronous one-time initialization. `INIT_ONCE_INIT_FAILED` in
+    /// `dwFlags` resets the `INIT_ONCE` so that initialization can be retried.
+    #[allow(non_snake_case)]
+    fn InitOnceComplete(
+        &mut self,
+        init_once_op: OpTy<'tcx, Tag>, // LPINIT_ONCE
+        flags_op: OpTy<'tcx, Tag>, // DWORD
+        context_op: OpTy<'tcx, Tag>, // LPVOID
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "InitOnceComplete");
+
+        let (init_once, state) = this.once_place(init_once_op)?;
+        let flags = this.read_scalar(flags_op)?.to_u32()?;
+        let context = this.read_scalar(context_op)?.not_undef()?;
+        if flags & !INIT_ONCE_INIT_FAILED != 0 {
+            throw_unsup_format!("unsupported `dwFlags` value for `InitOnceComplete`: {:#x}", flags);
+        }
+        if !this.is_null(context)? {
+            throw_unsup_format!("storing a context in an `INIT_ONCE` is not supported");
+        }
+
+        if state != ONCE_IN_PROGRESS {
+            // Nobody called `InitOnceBeginInitialize`.
+            this.set_last_error(Scalar::from_u32(ERROR_GEN_FAILURE))?;
+            return Ok(0);
+        }
+        let state = if flags & INIT_ONCE_INIT_FAILED != 0 { ONCE_INIT } else { ONCE_DONE };
+        this.set_once_state(init_once, state)?;
+        Ok(1)
+    }
+
+    /// Completes a once object after its initialization function returned.
+    fn complete_once(&mut self, completion: OnceCompletion<'tcx>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let succeeded = match completion.status {
+            Some(status) => {
+                let succeeded = this.read_scalar(status.into())?.to_i32()? != 0;
+                // Normalize the result to `TRUE`/`FALSE`.
+                this.write_scalar(Scalar::from_i32(succeeded.into()), status)?;
+                succeeded
+            }
+            None => true,
+        };
+        this.set_once_state(completion.once, if succeeded { ONCE_DONE } else { ONCE_INIT })
+    }
+
+    /// Implements the `Interlocked*` functions (and their `64` variants) as sequentially
+    /// consistent atomic operations on the `LONG`/`LONG64` that the first argument points to.
+    fn windows_interlocked(
+        &mut self,
+        link_name: &str,
+        args: &[OpTy<'tcx, Tag>],
+        dest: PlaceTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", link_name);
+
+        let place = this.deref_operand(args[0])?;
+        // Like all atomics, these must be aligned to their size.
+        let align = Align::from_bytes(place.layout.size.bytes()).unwrap();
+        this.memory.check_ptr_access(place.ptr, place.layout.size, align)?;
+        this.check_atomic_access_size(place)?;
+
+        let old = this.read_immediate(place.into())?;
+        let one = ImmTy::from_scalar(Scalar::from_int(1, old.layout.size), old.layout);
+        // The new value (if any), and the value that gets returned.
+        let (new, result) = match link_name.trim_end_matches("64") {
+            "InterlockedExchange" => (Some(this.read_immediate(args[1])?), old),
+            "InterlockedExchangeAdd" => {
+                let new = this.binary_op(mir::BinOp::Add, old, this.read_immediate(args[1])?)?;
+                (Some(new), old)
+            }
+            "InterlockedIncrement" => {
+                let new = this.binary_op(mir::BinOp::Add, old, one)?;
+                (Some(new), new)
+            }
+            "InterlockedDecrement" => {
+                let new = this.binary_op(mir::BinOp::Sub, old, one)?;
+                (Some(new), new)
+            }
+            "InterlockedCompareExchange" => {
+                // The exchange value comes before the comparand.
+                let exchange = this.read_immediate(args[1])?;
+                let comparand = this.read_immediate(args[2])?;
+                let eq = this.overflowing_binary_op(mir::BinOp::Eq, old, comparand)?.0;
+                (if eq.to_bool()? { Some(exchange) } else { None }, old)
+            }
+            _ => bug!("`windows_interlocked` called with unknown function {}", link_name),
+        };
+
+        this.write_immediate(*result, dest)?;
+        if let Some(new) = new {
+            this.write_immediate(*new, place.into())?;
+        }
+        this.record_atomic_access_size(place)
+    }
+
+    /// Creates a (possibly named) mutex and returns a handle to it, or opens the existing mutex
+    /// with that name.
+    #[allow(non_snake_case)]
+    fn CreateMutexW(
+        &mut self,
+        attributes_op: OpTy<'tcx, Tag>, // LPSECURITY_ATTRIBUTES
+        initial_owner_op: OpTy<'tcx, Tag>, // BOOL
+        name_op: OpTy<'tcx, Tag>, // LPCWSTR
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "CreateMutexW");
+
+        // Security attributes only matter for other processes, so we ignore them.
+        let _attributes = this.read_scalar(attributes_op)?.not_undef()?;
+        let initial_owner = this.read_scalar(initial_owner_op)?.to_i32()? != 0;
+        let name = this.read_scalar(name_op)?.not_undef()?;
+        let name = if this.is_null(name)? { None } else { Some(this.memory.read_wide_str(name)?) };
+
+        let mutexes = &mut this.machine.windows_mutexes;
+        if let Some(&mutex) = name.as_ref().and_then(|name| mutexes.names.get(name)) {
+            // The existing mutex is not acquired, even if `bInitialOwner` is set.
+            let handle = mutexes.insert_handle(mutex);
+            this.set_last_error(Scalar::from_u32(ERROR_ALREADY_EXISTS))?;
+            return Ok(handle);
+        }
+
+        let mutex = mutexes.next_mutex;
+        mutexes.next_mutex += 1;
+        if let Some(name) = &name {
+            mutexes.names.insert(name.clone(), mutex);
+        }
+        mutexes.mutexes.insert(mutex, WindowsMutex { name, lock_count: u32::from(initial_owner) });
+        let handle = mutexes.insert_handle(mutex);
+        // Programs check the last error to find out whether the mutex already existed, so it
+        // has to be reset.
+        this.set_last_error(Scalar::from_u32(0))?;
+        Ok(handle)
+    }
+
+    /// Returns a new handle to the mutex with the given name, or `NULL` if there is none.
+    #[allow(non_snake_case)]
+    fn OpenMutexW(
+        &mut self,
+        access_op: OpTy<'tcx, Tag>, // DWORD
+        inherit_op: OpTy<'tcx, Tag>, // BOOL
+        name_op: OpTy<'tcx, Tag>, // LPCWSTR
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "OpenMutexW");
+
+        let _access = this.read_scalar(access_op)?.to_u32()?;
+        let _inherit = this.read_scalar(inherit_op)?.to_i32()?;
+        let name = this.read_scalar(name_op)?.not_undef()?;
+        let name = this.memory.read_wide_str(name)?;
+
+        let mutexes = &mut this.machine.windows_mutexes;
+        match mutexes.names.get(&name) {
+            Some(&mutex) => Ok(mutexes.insert_handle(mutex)),
+            None => {
+                this.set_last_error(Scalar::from_u32(ERROR_FILE_NOT_FOUND))?;
+                Ok(0)
+            }
+        }
+    }
+
+    /// Acquires the mutex that `handle` refers to. As the current thread is the only one, the
+    /// mutex is either free or already owned by it, so this never has to wait.
+    #[allow(non_snake_case)]
+    fn WaitForSingleObject(
+        &mut self,
+        handle_op: OpTy<'tcx, Tag>, // HANDLE
+        timeout_op: OpTy<'tcx, Tag>, // DWORD
+    ) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "WaitForSingleObject");
+
+        let handle = this.read_scalar(handle_op)?.to_machine_isize(this)?;
+        let _timeout = this.read_scalar(timeout_op)?.to_u32()?;
+
+        let mutex = this.machine.windows_mutexes.mutex_mut(handle, "WaitForSingleObject")?;
+        mutex.lock_count = mutex
+            .lock_count
+            .checked_add(1)
+            .ok_or_else(|| err_unsup_format!("mutex was acquired too many times"))?;
+        Ok(WAIT_OBJECT_0)
+    }
+
+    /// Releases the mutex that `handle` refers to once. Returns `FALSE` if it is not held.
+    #[allow(non_snake_case)]
+    fn ReleaseMutex(
+        &mut self,
+        handle_op: OpTy<'tcx, Tag>, // HANDLE
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "ReleaseMutex");
+
+        let handle = this.read_scalar(handle_op)?.to_machine_isize(this)?;
+
+        let mutex = this.machine.windows_mutexes.mutex_mut(handle, "ReleaseMutex")?;
+        if mutex.lock_count == 0 {
+            this.set_last_error(Scalar::from_u32(ERROR_NOT_OWNER))?;
+            return Ok(0);
+        }
+        mutex.lock_count -= 1;
+        Ok(1)
+    }
+
+    /// Closes a mutex handle. The mutex is destroyed once its last handle is closed.
+    #[allow(non_snake_case)]
+    fn CloseHandle(
+        &mut self,
+        handle_op: OpTy<'tcx, Tag>, // HANDLE
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "CloseHandle");
+
+        let handle = this.read_scalar(handle_op)?.to_machine_isize(this)?;
+
+        let mutexes = &mut this.machine.windows_mutexes;
+        match mutexes.handles.remove(&handle) {
+            Some(mutex) => mutexes.remove_if_unused(mutex),
+            None => throw_unsup_format!("`CloseHandle` is only supported on mutex handles"),
+        }
+        Ok(1)
+    }
+}
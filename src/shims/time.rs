@@ -5,6 +5,9 @@ use crate::stacked_borrows::Tag;
 use crate::*;
 use helpers::immty_from_int_checked;
 
+/// The timeout value meaning "wait forever" on Windows.
+const INFINITE: u32 = 0xFFFFFFFF;
+
 /// Returns the time elapsed between the provided time and the unix epoch as a `Duration`.
 pub fn system_time_to_duration<'tcx>(time: &SystemTime) -> InterpResult<'tcx, Duration> {
     time.duration_since(SystemTime::UNIX_EPOCH)
@@ -32,6 +35,12 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             // Absolute time does not matter, only relative time does, so we can just
             // use our own time anchor here.
             Instant::now().duration_since(this.machine.time_anchor)
+        } else if clk_id == this.eval_libc_i32("CLOCK_PROCESS_CPUTIME_ID")?
+            || clk_id == this.eval_libc_i32("CLOCK_THREAD_CPUTIME_ID")?
+        {
+            // We do not track CPU time. There is only one thread, and the interpreter is busy
+            // for the entire run, so we approximate it by the time since startup.
+            Instant::now().duration_since(this.machine.time_anchor)
         } else {
             let einval = this.eval_libc("EINVAL")?;
             this.set_last_error(einval)?;
@@ -85,6 +94,157 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         Ok(0)
     }
 
+    fn nanosleep(
+        &mut self,
+        req_op: OpTy<'tcx, Tag>,
+        rem_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.check_no_isolation("nanosleep")?;
+
+        let req = this.read_scalar(req_op)?.not_undef()?;
+        // If the request pointer is null, the function fails with `EFAULT`.
+        if this.is_null(req)? {
+            let efault = this.eval_libc("EFAULT")?;
+            this.set_last_error(efault)?;
+            return Ok(-1);
+        }
+
+        let duration = match this.read_timespec(this.deref_operand(req_op)?)? {
+            Some(duration) => duration,
+            None => {
+                let einval = this.eval_libc("EINVAL")?;
+                this.set_last_error(einval)?;
+                return Ok(-1);
+            }
+        };
+
+        // A (simulated) interruption happens before any time passes, so all of it remains.
+        let (result, remaining) = if this.inject_eintr()? {
+            (-1, duration)
+        } else {
+            std::thread::sleep(duration);
+            (0, Duration::new(0, 0))
+        };
+
+        let rem = this.read_scalar(rem_op)?.not_undef()?;
+        if !this.is_null(rem)? {
+            let imms = [
+                immty_from_int_checked(remaining.as_secs(), this.libc_ty_layout("time_t")?)?,
+                immty_from_int_checked(remaining.subsec_nanos(), this.libc_ty_layout("c_long")?)?,
+            ];
+            this.write_packed_immediates(this.deref_operand(rem_op)?, &imms)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Like `nanosleep`, but `request` can also be an absolute time of the given clock. Errors are
+    /// returned instead of being stored in `errno`.
+    fn clock_nanosleep(
+        &mut self,
+        clk_id_op: OpTy<'tcx, Tag>,
+        flags_op: OpTy<'tcx, Tag>,
+        req_op: OpTy<'tcx, Tag>,
+        rem_op: OpTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        this.assert_target_os("linux", "clock_nanosleep");
+        this.check_no_isolation("clock_nanosleep")?;
+
+        let clk_id = this.read_scalar(clk_id_op)?.to_i32()?;
+        let flags = this.read_scalar(flags_op)?.to_i32()?;
+        let timer_abstime = this.eval_libc_i32("TIMER_ABSTIME")?;
+        if flags & !timer_abstime != 0 {
+            return this.eval_libc_i32("EINVAL");
+        }
+        let absolute = flags & timer_abstime != 0;
+
+        let req = this.read_scalar(req_op)?.not_undef()?;
+        if this.is_null(req)? {
+            return this.eval_libc_i32("EFAULT");
+        }
+        let request = match this.read_timespec(this.deref_operand(req_op)?)? {
+            Some(request) => request,
+            None => return this.eval_libc_i32("EINVAL"),
+        };
+
+        // For absolute times, we sleep until the clock (as `clock_gettime` reports it) has
+        // reached `request`.
+        let now = if clk_id == this.eval_libc_i32("CLOCK_REALTIME")? {
+            system_time_to_duration(&SystemTime::now())?
+        } else if clk_id == this.eval_libc_i32("CLOCK_MONOTONIC")? {
+            Instant::now().duration_since(this.machine.time_anchor)
+        } else {
+            return this.eval_libc_i32("EINVAL");
+        };
+        let duration = if absolute {
+            request.checked_sub(now).unwrap_or(Duration::new(0, 0))
+        } else {
+            request
+        };
+
+        // `inject_eintr` also sets `errno`, which is harmless.
+        let (result, remaining) = if this.inject_eintr()? {
+            (this.eval_libc_i32("EINTR")?, duration)
+        } else {
+            std::thread::sleep(duration);
+            (0, Duration::new(0, 0))
+        };
+
+        // The remaining time is only reported for relative sleeps; an absolute sleep can just be
+        // restarted with the same `request`.
+        let rem = this.read_scalar(rem_op)?.not_undef()?;
+        if !absolute && !this.is_null(rem)? {
+            let imms = [
+                immty_from_int_checked(remaining.as_secs(), this.libc_ty_layout("time_t")?)?,
+                immty_from_int_checked(remaining.subsec_nanos(), this.libc_ty_layout("c_long")?)?,
+            ];
+            this.write_packed_immediates(this.deref_operand(rem_op)?, &imms)?;
+        }
+
+        Ok(result)
+    }
+
+    #[allow(non_snake_case)]
+    fn Sleep(&mut self, timeout_op: OpTy<'tcx, Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.assert_target_os("windows", "Sleep");
+
+        let timeout_ms = this.read_scalar(timeout_op)?.to_u32()?;
+        match timeout_ms {
+            // There is no other thread that could ever wake us up.
+            INFINITE => throw_machine_stop!(TerminationInfo::Deadlock),
+            // This only gives up the rest of the time slice, and there is no other thread to run.
+            0 => {}
+            _ => {
+                this.check_no_isolation("Sleep")?;
+                std::thread::sleep(Duration::from_millis(timeout_ms.into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a `timespec` and converts it to a `Duration`. Returns `None` if the value is not a
+    /// valid `timespec`, i.e., if `tv_sec` is negative or `tv_nsec` is not in `0..1_000_000_000`.
+    fn read_timespec(
+        &mut self,
+        timespec: MPlaceTy<'tcx, Tag>,
+    ) -> InterpResult<'tcx, Option<Duration>> {
+        let this = self.eval_context_mut();
+
+        // `time_t` and `c_long` are both pointer-sized on all targets we support.
+        let tv_sec = this.read_scalar(this.mplace_field(timespec, 0)?.into())?.to_machine_isize(this)?;
+        let tv_nsec = this.read_scalar(this.mplace_field(timespec, 1)?.into())?.to_machine_isize(this)?;
+        Ok(match (u64::try_from(tv_sec), u32::try_from(tv_nsec)) {
+            (Ok(tv_sec), Ok(tv_nsec)) if tv_nsec < 1_000_000_000 =>
+                Some(Duration::new(tv_sec, tv_nsec)),
+            _ => None,
+        })
+    }
+
     fn mach_absolute_time(&self) -> InterpResult<'tcx, u64> {
         let this = self.eval_context_ref();
 
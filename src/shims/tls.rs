@@ -11,6 +11,9 @@ use crate::{HelpersEvalContextExt, InterpResult, MPlaceTy, Scalar, StackPopClean
 
 pub type TlsKey = u128;
 
+/// The number of times we go over all keys when running destructors, as on Linux and macOS.
+const PTHREAD_DESTRUCTOR_ITERATIONS: u32 = 4;
+
 #[derive(Copy, Clone, Debug)]
 pub struct TlsEntry<'tcx> {
     /// The data for this key. None is used to represent NULL.
@@ -181,7 +184,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             )?;
 
             // step until out of stackframes
-            this.run()?;
+            while this.step_with_bookkeeping()? {}
 
             // Windows doesn't have other destructors.
             return Ok(());
@@ -200,10 +203,11 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             )?;
 
             // step until out of stackframes
-            this.run()?;
+            while this.step_with_bookkeeping()? {}
         }
 
         // Now run the "keyed" destructors.
+        let mut iterations = 1;
         let mut dtor = this.machine.tls.fetch_tls_dtor(None);
         while let Some((instance, ptr, key)) = dtor {
             trace!("Running TLS dtor {:?} on {:?}", instance, ptr);
@@ -218,13 +222,18 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             )?;
 
             // step until out of stackframes
-            this.run()?;
+            while this.step_with_bookkeeping()? {}
 
             // Fetch next dtor after `key`.
             dtor = match this.machine.tls.fetch_tls_dtor(Some(key)) {
                 dtor @ Some(_) => dtor,
+                // Destructors keep setting values, so we give up like the real implementations do.
+                None if iterations == PTHREAD_DESTRUCTOR_ITERATIONS => None,
                 // We ran each dtor once, start over from the beginning.
-                None => this.machine.tls.fetch_tls_dtor(None),
+                None => {
+                    iterations += 1;
+                    this.machine.tls.fetch_tls_dtor(None)
+                }
             };
         }
         Ok(())
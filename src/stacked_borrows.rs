@@ -60,6 +60,12 @@ pub struct Item {
     protector: Option<CallId>,
 }
 
+impl Item {
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+}
+
 impl fmt::Debug for Item {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[{:?} for {:?}", self.perm, self.tag)?;
@@ -0,0 +1,6 @@
+// ignore-windows: No libc on Windows
+// error-pattern: the evaluated program aborted execution: the program called `abort()`
+
+fn main() {
+    std::process::abort();
+}
@@ -0,0 +1,8 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+fn main() {
+    let x = AtomicU64::new(0);
+    x.store(42, Ordering::SeqCst);
+    let halves = unsafe { &*(&x as *const AtomicU64 as *const [AtomicU32; 2]) };
+    let _val = halves[1].load(Ordering::SeqCst); //~ ERROR mixed-size atomic access: previously accessed as 8-byte atomic, now as 4-byte atomic
+}
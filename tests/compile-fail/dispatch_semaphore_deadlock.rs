@@ -0,0 +1,18 @@
+// ignore-linux: Uses macOS-only APIs
+// ignore-windows: Uses macOS-only APIs
+
+use std::os::raw::{c_long, c_void};
+
+extern "C" {
+    fn dispatch_semaphore_create(value: c_long) -> *mut c_void;
+    fn dispatch_semaphore_wait(sema: *mut c_void, timeout: u64) -> c_long;
+}
+
+const DISPATCH_TIME_FOREVER: u64 = !0;
+
+fn main() {
+    unsafe {
+        let sema = dispatch_semaphore_create(0);
+        dispatch_semaphore_wait(sema, DISPATCH_TIME_FOREVER); //~ ERROR the evaluated program deadlocked
+    }
+}
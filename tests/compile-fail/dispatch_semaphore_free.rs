@@ -0,0 +1,21 @@
+// ignore-linux: Uses macOS-only APIs
+// ignore-windows: Uses macOS-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::os::raw::{c_long, c_void};
+
+extern "C" {
+    fn dispatch_semaphore_create(value: c_long) -> *mut c_void;
+}
+
+// error-pattern: deallocating `Machine(Dispatch)` memory using `Machine(C)` deallocation operation
+
+fn main() {
+    unsafe {
+        let sema = dispatch_semaphore_create(0);
+        libc::free(sema);
+    }
+}
@@ -0,0 +1,12 @@
+// compile-flags: -Zmiri-ignore-asm
+#![feature(llvm_asm)]
+
+fn read_zero() -> u32 {
+    let x: u32;
+    unsafe { llvm_asm!("" : "=r"(x)) } //~ ERROR skipping inline assembly with outputs is not supported
+    x
+}
+
+fn main() {
+    read_zero();
+}
@@ -0,0 +1,9 @@
+#![feature(llvm_asm)]
+
+fn barrier() {
+    unsafe { llvm_asm!("" :::: "volatile") } //~ ERROR inline assembly is not supported
+}
+
+fn main() {
+    barrier();
+}
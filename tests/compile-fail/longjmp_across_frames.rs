@@ -0,0 +1,24 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+type JmpBuf = [libc::c_long; 32];
+
+extern "C" {
+    fn setjmp(env: *mut JmpBuf) -> libc::c_int;
+    fn longjmp(env: *mut JmpBuf, val: libc::c_int) -> !;
+}
+
+fn jump(env: &mut JmpBuf) {
+    let _guard = vec![1, 2, 3];
+    unsafe { longjmp(env, 1) } //~ ERROR longjmp across Rust stack frames
+}
+
+fn main() {
+    let mut env: JmpBuf = [0; 32];
+    if unsafe { setjmp(&mut env) } == 0 {
+        jump(&mut env);
+    }
+}
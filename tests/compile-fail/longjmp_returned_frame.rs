@@ -0,0 +1,27 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+type JmpBuf = [libc::c_long; 32];
+
+extern "C" {
+    fn setjmp(env: *mut JmpBuf) -> libc::c_int;
+    fn longjmp(env: *mut JmpBuf, val: libc::c_int) -> !;
+}
+
+fn save(env: &mut JmpBuf) {
+    unsafe { setjmp(env) };
+}
+
+fn jump(env: &mut JmpBuf) {
+    // This frame is at the same depth as the frame of `save`, which has returned.
+    unsafe { longjmp(env, 1) } //~ ERROR `longjmp` into a function that has already returned
+}
+
+fn main() {
+    let mut env: JmpBuf = [0; 32];
+    save(&mut env);
+    jump(&mut env);
+}
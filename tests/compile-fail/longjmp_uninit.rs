@@ -0,0 +1,20 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+type JmpBuf = [libc::c_long; 32];
+
+extern "C" {
+    fn setjmp(env: *mut JmpBuf) -> libc::c_int;
+    fn longjmp(env: *mut JmpBuf, val: libc::c_int) -> !;
+}
+
+fn main() {
+    let mut env: JmpBuf = [0; 32];
+    let mut other: JmpBuf = [0; 32];
+    if unsafe { setjmp(&mut env) } == 0 {
+        unsafe { longjmp(&mut other, 1) } //~ ERROR not initialized by `setjmp`
+    }
+}
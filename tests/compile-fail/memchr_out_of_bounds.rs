@@ -0,0 +1,11 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    let buf = [0u8; 4];
+    // The byte is not in the buffer, so `memchr` reads past its end.
+    unsafe { libc::memchr(buf.as_ptr().cast(), 1, 5) }; //~ ERROR outside bounds of alloc
+}
@@ -0,0 +1,22 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ptr;
+
+fn main() {
+    unsafe {
+        let ptr = libc::mmap(
+            ptr::null_mut(),
+            4096,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANON,
+            -1,
+            0,
+        );
+        assert_eq!(libc::mprotect(ptr, 4096, libc::PROT_NONE), 0);
+        let _val = *(ptr as *const u8); //~ ERROR access to PROT_NONE memory
+    }
+}
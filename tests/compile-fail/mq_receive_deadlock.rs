@@ -0,0 +1,16 @@
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ptr;
+
+fn main() {
+    unsafe {
+        let mqd = libc::mq_open(b"/miri-empty\0".as_ptr().cast(), libc::O_RDONLY | libc::O_CREAT, 0o600, ptr::null::<libc::mq_attr>());
+        let mut buf = [0u8; 8192];
+        libc::mq_receive(mqd, buf.as_mut_ptr().cast(), buf.len(), ptr::null_mut()); //~ ERROR the evaluated program deadlocked
+    }
+}
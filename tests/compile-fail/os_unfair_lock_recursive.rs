@@ -0,0 +1,19 @@
+// ignore-linux: Uses macOS-only APIs
+// ignore-windows: Uses macOS-only APIs
+
+#[repr(C)]
+struct OsUnfairLock {
+    _opaque: u32,
+}
+
+extern "C" {
+    fn os_unfair_lock_lock(lock: *mut OsUnfairLock);
+}
+
+fn main() {
+    let mut lock = OsUnfairLock { _opaque: 0 };
+    unsafe {
+        os_unfair_lock_lock(&mut lock);
+        os_unfair_lock_lock(&mut lock); //~ ERROR trying to recursively lock an `os_unfair_lock`
+    }
+}
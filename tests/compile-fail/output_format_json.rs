@@ -0,0 +1,11 @@
+// compile-flags: -Zmiri-output-format=json
+// error-pattern: {"kind":"Undefined Behavior","level":"error","message":"pointer to alloc
+// error-pattern: "thread_id":0,"thread_name":"main","frames":[{"function":"main","file":
+// error-pattern: "tags":null}
+
+fn main() {
+    let x = Box::new(42);
+    let ptr = &*x as *const i32;
+    drop(x);
+    unsafe { *ptr };
+}
@@ -0,0 +1,15 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        let mut fds = [-1; 2];
+        libc::pipe(fds.as_mut_ptr());
+        let mut buf = [0u8; 8];
+        // Nothing can ever write to the pipe while this read waits.
+        libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()); //~ ERROR the evaluated program deadlocked
+    }
+}
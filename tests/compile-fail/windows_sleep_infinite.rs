@@ -0,0 +1,14 @@
+// ignore-linux: Uses Windows-only APIs
+// ignore-macos: Uses Windows-only APIs
+
+extern "system" {
+    fn Sleep(milliseconds: u32);
+}
+
+const INFINITE: u32 = 0xFFFFFFFF;
+
+fn main() {
+    unsafe {
+        Sleep(INFINITE); //~ ERROR the evaluated program deadlocked
+    }
+}
@@ -0,0 +1,18 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+extern "C" fn handler() {
+    println!("handler");
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::atexit(handler), 0);
+    }
+    println!("main");
+    // `exit` runs the handlers, even though `main` does not return.
+    std::process::exit(0);
+}
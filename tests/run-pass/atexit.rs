@@ -0,0 +1,28 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn first() {
+    // Handlers run in reverse order of registration.
+    assert_eq!(CALLS.fetch_add(1, Ordering::Relaxed), 1);
+    println!("first");
+}
+
+extern "C" fn second() {
+    assert_eq!(CALLS.fetch_add(1, Ordering::Relaxed), 0);
+    println!("second");
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::atexit(first), 0);
+        assert_eq!(libc::atexit(second), 0);
+    }
+    println!("main");
+}
@@ -0,0 +1,13 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+fn main() {
+    let mut x = AtomicU64::new(0);
+    x.fetch_add(1, Ordering::SeqCst);
+    // A non-atomic write ends the previous atomic object, so the memory may be reused
+    // with a different atomic size.
+    unsafe { *(&mut x as *mut AtomicU64 as *mut u64) = 0 };
+    let halves = unsafe { &*(&x as *const AtomicU64 as *const [AtomicU32; 2]) };
+    halves[0].store(1, Ordering::SeqCst);
+    halves[1].store(2, Ordering::SeqCst);
+    assert_eq!(halves[0].load(Ordering::SeqCst) + halves[1].load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,25 @@
+// ignore-windows: No libc on Windows
+// ignore-macos: Uses Linux-only APIs
+// compile-flags: -Zmiri-disable-isolation
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn cputime(clock: libc::clockid_t) -> libc::timespec {
+    let mut tp = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    assert_eq!(unsafe { libc::clock_gettime(clock, &mut tp) }, 0);
+    assert!(tp.tv_sec >= 0);
+    assert!(0 <= tp.tv_nsec && tp.tv_nsec < 1_000_000_000);
+    tp
+}
+
+fn main() {
+    for &clock in &[libc::CLOCK_PROCESS_CPUTIME_ID, libc::CLOCK_THREAD_CPUTIME_ID] {
+        let before = cputime(clock);
+        // Do some work to make time pass.
+        for _ in 0..10 { drop(vec![42]); }
+        let after = cputime(clock);
+        assert!((after.tv_sec, after.tv_nsec) > (before.tv_sec, before.tv_nsec));
+    }
+}
@@ -0,0 +1,61 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+// compile-flags: -Zmiri-disable-isolation
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::mem;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+fn now(clock: libc::clockid_t) -> libc::timespec {
+    let mut ts: libc::timespec = unsafe { mem::zeroed() };
+    assert_eq!(unsafe { libc::clock_gettime(clock, &mut ts) }, 0);
+    ts
+}
+
+fn test_errors() {
+    let req = libc::timespec { tv_sec: 0, tv_nsec: 1_000_000_000 };
+    // Errors are returned, not stored in `errno`.
+    assert_eq!(unsafe { libc::clock_nanosleep(libc::CLOCK_MONOTONIC, 0, &req, ptr::null_mut()) }, libc::EINVAL);
+    let req = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    assert_eq!(unsafe { libc::clock_nanosleep(libc::CLOCK_PROCESS_CPUTIME_ID, 0, &req, ptr::null_mut()) }, libc::EINVAL);
+    assert_eq!(unsafe { libc::clock_nanosleep(libc::CLOCK_MONOTONIC, 42, &req, ptr::null_mut()) }, libc::EINVAL);
+}
+
+fn test_relative() {
+    let req = libc::timespec { tv_sec: 0, tv_nsec: 10_000_000 };
+    let mut rem = libc::timespec { tv_sec: 42, tv_nsec: 42 };
+    let before = Instant::now();
+    assert_eq!(unsafe { libc::clock_nanosleep(libc::CLOCK_REALTIME, 0, &req, &mut rem) }, 0);
+    assert!(before.elapsed() >= Duration::from_millis(10));
+    assert_eq!((rem.tv_sec, rem.tv_nsec), (0, 0));
+}
+
+fn test_absolute() {
+    for &clock in [libc::CLOCK_MONOTONIC, libc::CLOCK_REALTIME].iter() {
+        let mut req = now(clock);
+        req.tv_nsec += 10_000_000;
+        if req.tv_nsec >= 1_000_000_000 {
+            req.tv_sec += 1;
+            req.tv_nsec -= 1_000_000_000;
+        }
+        assert_eq!(unsafe { libc::clock_nanosleep(clock, libc::TIMER_ABSTIME, &req, ptr::null_mut()) }, 0);
+        let after = now(clock);
+        assert!((after.tv_sec, after.tv_nsec) >= (req.tv_sec, req.tv_nsec));
+    }
+
+    // Times in the past return immediately.
+    let req = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    assert_eq!(unsafe { libc::clock_nanosleep(libc::CLOCK_MONOTONIC, libc::TIMER_ABSTIME, &req, ptr::null_mut()) }, 0);
+}
+
+fn main() {
+    test_errors();
+    test_relative();
+    test_absolute();
+}
@@ -0,0 +1,34 @@
+// compile-flags: -Zmiri-cxchg-weak-failure-rate=0.5
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn increment(counter: &AtomicUsize) -> usize {
+    let mut current = counter.load(Ordering::Relaxed);
+    loop {
+        match counter.compare_exchange_weak(current, current + 1, Ordering::AcqRel, Ordering::Relaxed) {
+            Ok(_) => return current,
+            Err(actual) => current = actual,
+        }
+    }
+}
+
+fn main() {
+    let counter = AtomicUsize::new(0);
+    for i in 0..100 {
+        assert_eq!(increment(&counter), i);
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 100);
+
+    // Some of the attempts without a retry loop fail spuriously.
+    let failures = (0..100)
+        .filter(|_| {
+            let current = counter.load(Ordering::Relaxed);
+            counter.compare_exchange_weak(current, current + 1, Ordering::SeqCst, Ordering::SeqCst).is_err()
+        })
+        .count();
+    assert!(failures > 0 && failures < 100);
+
+    // The strong variant never fails spuriously.
+    let current = counter.load(Ordering::Relaxed);
+    assert_eq!(counter.compare_exchange(current, 0, Ordering::SeqCst, Ordering::SeqCst), Ok(current));
+}
@@ -0,0 +1,27 @@
+// ignore-linux: Uses macOS-only APIs
+// ignore-windows: Uses macOS-only APIs
+
+use std::os::raw::{c_long, c_void};
+
+type DispatchOnce = c_long;
+
+extern "C" {
+    fn dispatch_once_f(pred: *mut DispatchOnce, ctx: *mut c_void, function: extern "C" fn(*mut c_void));
+}
+
+extern "C" fn increment(ctx: *mut c_void) {
+    unsafe { *(ctx as *mut u32) += 1 };
+}
+
+fn main() {
+    let mut pred: DispatchOnce = 0;
+    let mut counter = 0u32;
+    unsafe {
+        dispatch_once_f(&mut pred, &mut counter as *mut u32 as *mut c_void, increment);
+        assert_eq!(counter, 1);
+        // The inline fast path of `dispatch_once` relies on this value.
+        assert_eq!(pred, !0);
+        dispatch_once_f(&mut pred, &mut counter as *mut u32 as *mut c_void, increment);
+    }
+    assert_eq!(counter, 1);
+}
@@ -0,0 +1,37 @@
+// ignore-linux: Uses macOS-only APIs
+// ignore-windows: Uses macOS-only APIs
+
+use std::os::raw::{c_long, c_void};
+
+type DispatchSemaphore = *mut c_void;
+
+const DISPATCH_TIME_NOW: u64 = 0;
+
+extern "C" {
+    fn dispatch_semaphore_create(value: c_long) -> DispatchSemaphore;
+    fn dispatch_semaphore_wait(sema: DispatchSemaphore, timeout: u64) -> c_long;
+    fn dispatch_semaphore_signal(sema: DispatchSemaphore) -> c_long;
+    fn dispatch_retain(object: DispatchSemaphore);
+    fn dispatch_release(object: DispatchSemaphore);
+}
+
+fn main() {
+    unsafe {
+        assert!(dispatch_semaphore_create(-1).is_null());
+
+        let sema = dispatch_semaphore_create(1);
+        assert!(!sema.is_null());
+        assert_eq!(dispatch_semaphore_wait(sema, DISPATCH_TIME_NOW), 0);
+        assert_ne!(dispatch_semaphore_wait(sema, DISPATCH_TIME_NOW), 0);
+        assert_eq!(dispatch_semaphore_signal(sema), 0);
+        assert_eq!(dispatch_semaphore_signal(sema), 0);
+        assert_eq!(dispatch_semaphore_wait(sema, !0), 0);
+        assert_eq!(dispatch_semaphore_wait(sema, !0), 0);
+
+        dispatch_retain(sema);
+        dispatch_release(sema);
+        // The semaphore is still alive.
+        assert_eq!(dispatch_semaphore_signal(sema), 0);
+        dispatch_release(sema);
+    }
+}
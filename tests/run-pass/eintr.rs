@@ -0,0 +1,41 @@
+// ignore-windows: No libc on Windows
+// compile-flags: -Zmiri-disable-isolation -Zmiri-eintr-rate=0.5
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::fs::{remove_file, File};
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+fn tmp() -> PathBuf {
+    std::env::var("MIRI_TEMP").map(PathBuf::from).unwrap_or_else(|_| std::env::temp_dir())
+}
+
+fn main() {
+    let path = tmp().join("miri_test_eintr.txt");
+    remove_file(&path).ok();
+
+    // `write_all` and `read_to_end` retry interrupted calls.
+    File::create(&path).unwrap().write_all(b"Hello, World!\n").unwrap();
+    let mut contents = Vec::new();
+    File::open(&path).unwrap().read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"Hello, World!\n");
+
+    // Raw calls see the interruptions.
+    let file = File::open(&path).unwrap();
+    let mut byte = 0u8;
+    loop {
+        let res = unsafe { libc::read(file.as_raw_fd(), &mut byte as *mut u8 as *mut libc::c_void, 1) };
+        if res == 1 {
+            break;
+        }
+        assert_eq!(res, -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINTR));
+    }
+    assert_eq!(byte, b'H');
+
+    remove_file(&path).unwrap();
+}
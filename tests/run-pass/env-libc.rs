@@ -0,0 +1,27 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ffi::CStr;
+
+fn getenv(name: &[u8]) -> Option<&'static [u8]> {
+    let ptr = unsafe { libc::getenv(name.as_ptr().cast()) };
+    if ptr.is_null() { None } else { Some(unsafe { CStr::from_ptr(ptr) }.to_bytes()) }
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::setenv(b"FOO\0".as_ptr().cast(), b"bar\0".as_ptr().cast(), 1), 0);
+        assert_eq!(getenv(b"FOO\0"), Some(&b"bar"[..]));
+
+        let mut string = *b"FOO=baz\0";
+        assert_eq!(libc::putenv(string.as_mut_ptr().cast()), 0);
+        assert_eq!(getenv(b"FOO\0"), Some(&b"baz"[..]));
+        assert_eq!(std::env::var("FOO"), Ok("baz".to_owned()));
+
+        assert_eq!(libc::unsetenv(b"FOO\0".as_ptr().cast()), 0);
+        assert_eq!(getenv(b"FOO\0"), None);
+    }
+}
@@ -0,0 +1,48 @@
+// ignore-windows: No libc on Windows
+// compile-flags: -Zmiri-disable-isolation
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::fs::{remove_file, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::PathBuf;
+
+fn tmp() -> PathBuf {
+    std::env::var("MIRI_TEMP").map(PathBuf::from).unwrap_or_else(|_| std::env::temp_dir())
+}
+
+fn main() {
+    let path = tmp().join("miri_test_fs_dup.txt");
+    remove_file(&path).ok();
+    let mut file = File::create(&path).unwrap();
+    file.write_all(b"Hello, World!\n").unwrap();
+    let fd = file.as_raw_fd();
+
+    unsafe {
+        // `dup` returns a new descriptor that shares the file offset.
+        let dup_fd = libc::dup(fd);
+        assert!(dup_fd > fd);
+        let mut dup_file = File::from_raw_fd(dup_fd);
+        assert_eq!(dup_file.seek(SeekFrom::Current(0)).unwrap(), 14);
+
+        // `dup2` into an open descriptor replaces it.
+        assert_eq!(libc::dup2(fd, dup_fd), dup_fd);
+        assert_eq!(libc::dup2(fd, fd), fd);
+        drop(dup_file);
+
+        // Invalid descriptors are reported with `EBADF`.
+        assert_eq!(libc::dup(-1), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EBADF));
+        assert_eq!(libc::dup2(fd, -1), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EBADF));
+    }
+    drop(file);
+
+    let mut contents = String::new();
+    File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "Hello, World!\n");
+    remove_file(&path).unwrap();
+}
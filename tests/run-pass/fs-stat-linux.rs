@@ -0,0 +1,48 @@
+// ignore-windows: No libc on Windows
+// ignore-macos: Uses Linux-only APIs
+// compile-flags: -Zmiri-disable-isolation
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ffi::CString;
+use std::fs::{remove_file, File};
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+fn tmp() -> PathBuf {
+    std::env::var("MIRI_TEMP").map(PathBuf::from).unwrap_or_else(|_| std::env::temp_dir())
+}
+
+fn main() {
+    let path = tmp().join("miri_test_fs_stat_linux.txt");
+    remove_file(&path).ok();
+    let mut file = File::create(&path).unwrap();
+    file.write_all(b"Hello, World!\n").unwrap();
+    let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+
+    unsafe {
+        // Not all fields get written, so start from a zeroed buffer.
+        let mut buf: libc::stat = std::mem::zeroed();
+        assert_eq!(libc::stat(c_path.as_ptr(), &mut buf), 0);
+        assert_eq!(buf.st_size, 14);
+        assert_eq!(buf.st_mode & libc::S_IFMT, libc::S_IFREG);
+        assert_eq!(buf.st_nlink, 1);
+        let ino = buf.st_ino;
+        assert_ne!(ino, 0);
+
+        // `stat` and `fstat` agree on the identity of the file.
+        let mut buf: libc::stat = std::mem::zeroed();
+        assert_eq!(libc::fstat(file.as_raw_fd(), &mut buf), 0);
+        assert_eq!(buf.st_size, 14);
+        assert_eq!(buf.st_ino, ino);
+
+        assert_eq!(libc::fstat(-1, &mut buf), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EBADF));
+    }
+
+    remove_file(&path).unwrap();
+}
@@ -0,0 +1,22 @@
+// ignore-windows: No libc on Windows
+#![feature(rustc_private)]
+extern crate libc;
+
+use std::io::Error;
+
+extern "C" {
+    fn getentropy(buf: *mut libc::c_void, buflen: libc::size_t) -> libc::c_int;
+}
+
+fn main() {
+    let mut buf = [0u8; 257];
+    unsafe {
+        assert_eq!(getentropy(buf.as_mut_ptr() as *mut libc::c_void, 256), 0);
+        assert!(buf[..256].iter().any(|&b| b != 0));
+        assert_eq!(buf[256], 0);
+
+        // Requests for more than 256 bytes are refused.
+        assert_eq!(getentropy(buf.as_mut_ptr() as *mut libc::c_void, 257), -1);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EIO));
+    }
+}
@@ -0,0 +1,14 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    // With isolation, the process id is fake but stable.
+    let pid = unsafe { libc::getpid() };
+    assert_eq!(pid, 1000);
+    assert_eq!(unsafe { libc::getpid() }, pid);
+    assert_eq!(std::process::id(), 1000);
+    assert_eq!(unsafe { libc::getppid() }, pid - 1);
+}
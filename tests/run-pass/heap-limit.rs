@@ -0,0 +1,33 @@
+//ignore-windows: Uses POSIX APIs
+// compile-flags: -Zmiri-heap-limit=4096
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::alloc::{alloc, dealloc, realloc, Layout};
+
+fn main() {
+    unsafe {
+        // Allocations fail once they would exceed the limit (the runtime allocates a little, too)...
+        let small = libc::malloc(2048);
+        assert!(!small.is_null());
+        assert!(libc::malloc(4096).is_null());
+        // ... and a failed reallocation leaves the old allocation intact.
+        *(small as *mut u8) = 42;
+        assert!(libc::realloc(small, 8192).is_null());
+        assert_eq!(*(small as *mut u8), 42);
+        let small = libc::realloc(small, 3000);
+        assert!(!small.is_null());
+        libc::free(small);
+        // Sizes that do not even fit next to what is already allocated fail as well.
+        assert!(libc::malloc(usize::MAX).is_null());
+
+        // Freed memory becomes available again, also for Rust allocations.
+        let layout = Layout::from_size_align(3000, 8).unwrap();
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+        assert!(alloc(Layout::from_size_align(2000, 1).unwrap()).is_null());
+        assert!(realloc(ptr, layout, 8192).is_null());
+        dealloc(ptr, layout);
+    }
+}
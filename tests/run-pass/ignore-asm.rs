@@ -0,0 +1,13 @@
+// compile-flags: -Zmiri-ignore-asm
+#![feature(llvm_asm)]
+
+fn barrier() {
+    unsafe { llvm_asm!("" :::: "volatile") }
+}
+
+fn main() {
+    let mut x = 1;
+    barrier();
+    x += 1;
+    assert_eq!(x, 2);
+}
@@ -0,0 +1,29 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn memchr(haystack: &[u8], c: u8) -> Option<usize> {
+    let found = unsafe { libc::memchr(haystack.as_ptr().cast(), c.into(), haystack.len()) };
+    if found.is_null() { None } else { Some(found as usize - haystack.as_ptr() as usize) }
+}
+
+fn memmem(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let found = unsafe {
+        libc::memmem(haystack.as_ptr().cast(), haystack.len(), needle.as_ptr().cast(), needle.len())
+    };
+    if found.is_null() { None } else { Some(found as usize - haystack.as_ptr() as usize) }
+}
+
+fn main() {
+    assert_eq!(memchr(b"hello", b'l'), Some(2));
+    assert_eq!(memchr(b"hello", b'z'), None);
+    assert_eq!(memchr(b"", b'a'), None);
+
+    assert_eq!(memmem(b"hello world", b"o w"), Some(4));
+    assert_eq!(memmem(b"hello world", b"world"), Some(6));
+    assert_eq!(memmem(b"hello world", b"worlds"), None);
+    assert_eq!(memmem(b"hello", b""), Some(0));
+    assert_eq!(memmem(b"", b"a"), None);
+}
@@ -0,0 +1,24 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        let src = [1u8, 2, 3, 4];
+        let mut dst = [0u8; 4];
+        let ret = libc::memcpy(dst.as_mut_ptr().cast(), src.as_ptr().cast(), 4);
+        assert_eq!(ret, dst.as_mut_ptr().cast());
+        assert_eq!(dst, [1, 2, 3, 4]);
+
+        // `memmove` supports overlapping regions in both directions.
+        let mut buf = [1u8, 2, 3, 4, 5];
+        let ptr = buf.as_mut_ptr();
+        libc::memmove(ptr.add(1).cast(), ptr.cast(), 4);
+        libc::memmove(ptr.cast(), ptr.add(2).cast(), 3);
+        // Adjacent regions do not overlap.
+        libc::memcpy(ptr.add(3).cast(), ptr.add(1).cast(), 2);
+        assert_eq!(buf, [2, 3, 4, 3, 4]);
+    }
+}
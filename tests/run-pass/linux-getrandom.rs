@@ -13,5 +13,12 @@ fn main() {
 
         assert_eq!(libc::getrandom(0 as *mut libc::c_void, 0 as libc::size_t, 0 as libc::c_uint), 0);
         assert_eq!(libc::getrandom(buf.as_mut_ptr() as *mut libc::c_void, 5 as libc::size_t, 0 as libc::c_uint), 5);
+
+        // Successive reads advance the PRNG, and `GRND_NONBLOCK` never has to wait.
+        let mut first = [0u8; 16];
+        let mut second = [0u8; 16];
+        assert_eq!(libc::getrandom(first.as_mut_ptr() as *mut libc::c_void, 16, libc::GRND_NONBLOCK), 16);
+        assert_eq!(libc::getrandom(second.as_mut_ptr() as *mut libc::c_void, 16, libc::GRND_NONBLOCK), 16);
+        assert_ne!(first, second);
     }
 }
@@ -0,0 +1,17 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        // The main thread has the same id as the process.
+        let tid = libc::syscall(libc::SYS_gettid);
+        assert_eq!(tid, libc::getpid() as libc::c_long);
+        assert_eq!(libc::syscall(libc::SYS_gettid), tid);
+    }
+}
@@ -0,0 +1,22 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::io::Error;
+use std::ptr;
+
+fn main() {
+    // `io_uring_setup` fails the same way, but also emits a diagnostic, which would make this
+    // test depend on the layout of the standard library's stack frames.
+    unsafe {
+        assert_eq!(libc::syscall(libc::SYS_io_uring_enter, 3, 1, 0, 0, ptr::null::<libc::c_void>(), 0), -1);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::ENOSYS));
+        assert_eq!(libc::syscall(libc::SYS_io_uring_register, 3, 0, ptr::null::<libc::c_void>(), 0), -1);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::ENOSYS));
+    }
+}
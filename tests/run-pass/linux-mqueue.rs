@@ -0,0 +1,67 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::io::Error;
+use std::mem;
+use std::ptr;
+
+fn errno() -> i32 {
+    Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn main() {
+    unsafe {
+        let name = b"/miri-test\0".as_ptr().cast();
+        let mut attr: libc::mq_attr = mem::zeroed();
+        attr.mq_maxmsg = 2;
+        attr.mq_msgsize = 16;
+        let mqd = libc::mq_open(name, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL | libc::O_NONBLOCK, 0o600, &attr);
+        assert!(mqd >= 0);
+        assert_eq!(libc::mq_open(name, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL, 0o600, &attr), -1);
+        assert_eq!(errno(), libc::EEXIST);
+        assert_eq!(libc::mq_open(b"/miri-missing\0".as_ptr().cast(), libc::O_RDONLY), -1);
+        assert_eq!(errno(), libc::ENOENT);
+
+        // Messages are received by priority, and in order within the same priority.
+        assert_eq!(libc::mq_send(mqd, b"low".as_ptr().cast(), 3, 1), 0);
+        assert_eq!(libc::mq_send(mqd, b"high".as_ptr().cast(), 4, 5), 0);
+        assert_eq!(libc::mq_send(mqd, b"full".as_ptr().cast(), 4, 0), -1);
+        assert_eq!(errno(), libc::EAGAIN);
+        assert_eq!(libc::mq_send(mqd, [0u8; 17].as_ptr().cast(), 17, 0), -1);
+        assert_eq!(errno(), libc::EMSGSIZE);
+
+        let mut current: libc::mq_attr = mem::zeroed();
+        assert_eq!(libc::mq_getattr(mqd, &mut current), 0);
+        assert_eq!(current.mq_flags, libc::O_NONBLOCK as libc::c_long);
+        assert_eq!(current.mq_maxmsg, 2);
+        assert_eq!(current.mq_msgsize, 16);
+        assert_eq!(current.mq_curmsgs, 2);
+
+        let mut buf = [0u8; 16];
+        let mut prio = 0;
+        assert_eq!(libc::mq_receive(mqd, buf.as_mut_ptr().cast(), buf.len(), &mut prio), 4);
+        assert_eq!(&buf[..4], b"high");
+        assert_eq!(prio, 5);
+        assert_eq!(libc::mq_receive(mqd, buf.as_mut_ptr().cast(), buf.len(), ptr::null_mut()), 3);
+        assert_eq!(&buf[..3], b"low");
+        assert_eq!(libc::mq_receive(mqd, buf.as_mut_ptr().cast(), buf.len(), ptr::null_mut()), -1);
+        assert_eq!(errno(), libc::EAGAIN);
+
+        // The queue stays usable through the descriptor after it has been unlinked.
+        assert_eq!(libc::mq_unlink(name), 0);
+        assert_eq!(libc::mq_unlink(name), -1);
+        assert_eq!(errno(), libc::ENOENT);
+        assert_eq!(libc::mq_send(mqd, b"x".as_ptr().cast(), 1, 0), 0);
+        assert_eq!(libc::mq_receive(mqd, buf.as_mut_ptr().cast(), buf.len(), ptr::null_mut()), 1);
+
+        assert_eq!(libc::mq_close(mqd), 0);
+        assert_eq!(libc::mq_close(mqd), -1);
+        assert_eq!(errno(), libc::EBADF);
+    }
+}
@@ -0,0 +1,32 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::io::Error;
+
+fn assert_einval(result: libc::c_int) {
+    assert_eq!(result, -1);
+    assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::prctl(libc::PR_GET_DUMPABLE), 1);
+        assert_eq!(libc::prctl(libc::PR_SET_DUMPABLE, 0), 0);
+        assert_eq!(libc::prctl(libc::PR_GET_DUMPABLE), 0);
+        assert_einval(libc::prctl(libc::PR_SET_DUMPABLE, 2));
+        assert_eq!(libc::prctl(libc::PR_GET_DUMPABLE), 0);
+        assert_eq!(libc::prctl(libc::PR_SET_DUMPABLE, 1), 0);
+        assert_eq!(libc::prctl(libc::PR_GET_DUMPABLE), 1);
+
+        assert_einval(libc::prctl(libc::PR_SET_SECCOMP, 2, 0));
+        assert_einval(libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1));
+        assert_einval(libc::prctl(libc::PR_CAP_AMBIENT, 1, 0, 0, 0));
+        assert_einval(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+    }
+}
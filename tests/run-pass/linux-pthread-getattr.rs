@@ -0,0 +1,32 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::mem::MaybeUninit;
+use std::ptr;
+
+fn main() {
+    unsafe {
+        let mut attr = MaybeUninit::<libc::pthread_attr_t>::uninit();
+        assert_eq!(libc::pthread_getattr_np(libc::pthread_self(), attr.as_mut_ptr()), 0);
+        let mut attr = attr.assume_init();
+
+        let mut stack_addr = ptr::null_mut();
+        let mut stack_size = 0;
+        assert_eq!(libc::pthread_attr_getstack(&attr, &mut stack_addr, &mut stack_size), 0);
+        assert!(stack_size > 0);
+        let mut size = 0;
+        assert_eq!(libc::pthread_attr_getstacksize(&attr, &mut size), 0);
+        assert_eq!(size, stack_size);
+        let mut state = -1;
+        assert_eq!(libc::pthread_attr_getdetachstate(&attr, &mut state), 0);
+        assert_eq!(state, libc::PTHREAD_CREATE_JOINABLE);
+
+        assert_eq!(libc::pthread_attr_destroy(&mut attr), 0);
+    }
+}
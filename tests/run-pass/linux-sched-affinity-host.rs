@@ -0,0 +1,19 @@
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+// compile-flags: -Zmiri-disable-isolation
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::mem;
+
+fn main() {
+    unsafe {
+        // With isolation disabled, the affinity of the host process is reported, which allows
+        // running on at least one CPU.
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        assert_eq!(libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set), 0);
+        assert!((0..libc::CPU_SETSIZE as usize).any(|cpu| libc::CPU_ISSET(cpu, &set)));
+    }
+}
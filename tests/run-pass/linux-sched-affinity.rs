@@ -0,0 +1,34 @@
+// Unfortunately, compiletest_rs does not support 'only-linux',
+// so we need to ignore Windows and macOS instead.
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::io::Error;
+use std::mem;
+
+fn main() {
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        libc::CPU_SET(5, &mut set);
+        assert_eq!(libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set), 0);
+        assert!(libc::CPU_ISSET(0, &set));
+        assert!(!libc::CPU_ISSET(1, &set));
+        assert!(!libc::CPU_ISSET(5, &set));
+
+        // The size must be a multiple of the word size.
+        assert_eq!(libc::sched_getaffinity(0, 3, &mut set), -1);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+        // Our own process can also be named by its ID, but there are no other processes.
+        assert_eq!(libc::sched_getaffinity(libc::getpid(), mem::size_of::<libc::cpu_set_t>(), &mut set), 0);
+        assert!(libc::CPU_ISSET(0, &set));
+        assert_eq!(libc::sched_getaffinity(libc::getpid() + 1, mem::size_of::<libc::cpu_set_t>(), &mut set), -1);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::ESRCH));
+
+        assert_eq!(libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set), 0);
+    }
+}
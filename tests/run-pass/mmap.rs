@@ -0,0 +1,42 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ptr;
+
+fn main() {
+    unsafe {
+        let len = 10000;
+        let ptr = libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANON,
+            -1,
+            0,
+        );
+        assert_ne!(ptr, libc::MAP_FAILED);
+        assert_eq!(ptr as usize % 4096, 0);
+
+        // The mapping is zeroed and rounded up to whole pages.
+        let bytes = std::slice::from_raw_parts_mut(ptr as *mut u8, 3 * 4096);
+        assert!(bytes.iter().all(|&b| b == 0));
+        bytes[3 * 4096 - 1] = 42;
+
+        assert_eq!(libc::munmap(ptr, len), 0);
+
+        // Empty mappings are rejected.
+        let ptr = libc::mmap(
+            ptr::null_mut(),
+            0,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANON,
+            -1,
+            0,
+        );
+        assert_eq!(ptr, libc::MAP_FAILED);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+    }
+}
@@ -0,0 +1,42 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ptr;
+
+fn main() {
+    unsafe {
+        let ptr = libc::mmap(
+            ptr::null_mut(),
+            2 * 4096,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANON,
+            -1,
+            0,
+        );
+        assert_ne!(ptr, libc::MAP_FAILED);
+        let bytes = ptr as *mut u8;
+        *bytes = 1;
+
+        // Read-only memory can still be read.
+        assert_eq!(libc::mprotect(ptr, 4096, libc::PROT_READ), 0);
+        assert_eq!(*bytes, 1);
+        // The second page is unaffected.
+        *bytes.add(4096) = 2;
+
+        // Guard pages do not affect the rest of the mapping.
+        assert_eq!(libc::mprotect(ptr, 4096, libc::PROT_NONE), 0);
+        assert_eq!(*bytes.add(4096), 2);
+
+        assert_eq!(libc::mprotect(ptr, 4096, libc::PROT_READ | libc::PROT_WRITE), 0);
+        *bytes = 3;
+
+        // The address must be page-aligned.
+        assert_eq!(libc::mprotect(bytes.add(1) as *mut libc::c_void, 1, libc::PROT_READ), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+        assert_eq!(libc::munmap(ptr, 2 * 4096), 0);
+    }
+}
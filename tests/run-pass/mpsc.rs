@@ -1,6 +1,25 @@
 #![feature(box_syntax)]
 
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, RecvError};
+
+fn disconnected() {
+    // Receiving after the last sender is gone must not block: buffered values are still
+    // delivered, and then the channel reports that it is disconnected.
+    let (tx, rx) = channel::<i32>();
+    let tx2 = tx.clone();
+    tx.send(1).unwrap();
+    drop(tx);
+    tx2.send(2).unwrap();
+    drop(tx2);
+    assert_eq!(rx.recv(), Ok(1));
+    assert_eq!(rx.recv(), Ok(2));
+    assert_eq!(rx.recv(), Err(RecvError));
+    assert_eq!(rx.recv(), Err(RecvError));
+
+    let (tx, rx) = channel::<i32>();
+    drop(tx);
+    assert_eq!(rx.recv(), Err(RecvError));
+}
 
 pub fn main() {
     let (tx, rx) = channel::<Box<_>>();
@@ -12,4 +31,6 @@ pub fn main() {
     tx.send(box 102).unwrap();
     assert_eq!(rx.recv().unwrap(), box 101);
     assert_eq!(rx.recv().unwrap(), box 102);
+
+    disconnected();
 }
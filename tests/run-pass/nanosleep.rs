@@ -0,0 +1,53 @@
+// ignore-windows: No libc on Windows
+// compile-flags: -Zmiri-disable-isolation
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::io::Error;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+fn last_errno() -> i32 {
+    Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn test_null_request() {
+    let res = unsafe { libc::nanosleep(ptr::null(), ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(last_errno(), libc::EFAULT);
+}
+
+fn test_invalid_request() {
+    let req = libc::timespec { tv_sec: 0, tv_nsec: 1_000_000_000 };
+    let res = unsafe { libc::nanosleep(&req, ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(last_errno(), libc::EINVAL);
+
+    let req = libc::timespec { tv_sec: -1, tv_nsec: 0 };
+    let res = unsafe { libc::nanosleep(&req, ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(last_errno(), libc::EINVAL);
+}
+
+fn test_sleep() {
+    let req = libc::timespec { tv_sec: 0, tv_nsec: 10_000_000 };
+    let mut rem = libc::timespec { tv_sec: 42, tv_nsec: 42 };
+    let before = Instant::now();
+    let res = unsafe { libc::nanosleep(&req, &mut rem) };
+    assert_eq!(res, 0);
+    assert!(before.elapsed() >= Duration::from_millis(10));
+    assert_eq!((rem.tv_sec, rem.tv_nsec), (0, 0));
+
+    // `std` uses `nanosleep` as well.
+    let before = Instant::now();
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(before.elapsed() >= Duration::from_millis(10));
+}
+
+fn main() {
+    test_null_request();
+    test_invalid_request();
+    test_sleep();
+}
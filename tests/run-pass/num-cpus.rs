@@ -0,0 +1,11 @@
+// ignore-windows: No libc on Windows
+// compile-flags: -Zmiri-num-cpus=4
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    assert_eq!(unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) }, 4);
+    assert_eq!(unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) }, 4);
+}
@@ -0,0 +1,33 @@
+// ignore-linux: Uses macOS-only APIs
+// ignore-windows: Uses macOS-only APIs
+
+#[repr(C)]
+struct OsUnfairLock {
+    _opaque: u32,
+}
+
+const OS_UNFAIR_LOCK_INIT: OsUnfairLock = OsUnfairLock { _opaque: 0 };
+
+extern "C" {
+    fn os_unfair_lock_lock(lock: *mut OsUnfairLock);
+    fn os_unfair_lock_trylock(lock: *mut OsUnfairLock) -> bool;
+    fn os_unfair_lock_unlock(lock: *mut OsUnfairLock);
+    fn os_unfair_lock_assert_owner(lock: *const OsUnfairLock);
+    fn os_unfair_lock_assert_not_owner(lock: *const OsUnfairLock);
+}
+
+fn main() {
+    let mut lock = OS_UNFAIR_LOCK_INIT;
+    unsafe {
+        os_unfair_lock_assert_not_owner(&lock);
+        os_unfair_lock_lock(&mut lock);
+        os_unfair_lock_assert_owner(&lock);
+        assert!(!os_unfair_lock_trylock(&mut lock));
+        os_unfair_lock_unlock(&mut lock);
+        os_unfair_lock_assert_not_owner(&lock);
+
+        assert!(os_unfair_lock_trylock(&mut lock));
+        os_unfair_lock_assert_owner(&lock);
+        os_unfair_lock_unlock(&mut lock);
+    }
+}
@@ -0,0 +1,50 @@
+// ignore-windows: No libc on Windows
+// compile-flags: -Zmiri-pipe-capacity=8
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::io::Error;
+
+fn errno() -> i32 {
+    Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn main() {
+    unsafe {
+        let mut fds = [-1; 2];
+        assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        // Bytes come out in the order they were written, and writes stop at the capacity.
+        assert_eq!(libc::write(write_fd, b"hello".as_ptr().cast(), 5), 5);
+        assert_eq!(libc::write(write_fd, b" world".as_ptr().cast(), 6), 3);
+        let mut buf = [0u8; 16];
+        assert_eq!(libc::read(read_fd, buf.as_mut_ptr().cast(), 4), 4);
+        assert_eq!(&buf[..4], b"hell");
+        assert_eq!(libc::read(read_fd, buf.as_mut_ptr().cast(), buf.len()), 4);
+        assert_eq!(&buf[..4], b"o wo");
+
+        // Each end only works in its own direction.
+        assert_eq!(libc::read(write_fd, buf.as_mut_ptr().cast(), buf.len()), -1);
+        assert_eq!(errno(), libc::EBADF);
+        assert_eq!(libc::write(read_fd, buf.as_ptr().cast(), 1), -1);
+        assert_eq!(errno(), libc::EBADF);
+
+        // Once the write end is closed, the remaining bytes can be read, followed by end of file.
+        assert_eq!(libc::write(write_fd, b"!".as_ptr().cast(), 1), 1);
+        assert_eq!(libc::close(write_fd), 0);
+        assert_eq!(libc::read(read_fd, buf.as_mut_ptr().cast(), buf.len()), 1);
+        assert_eq!(buf[0], b'!');
+        assert_eq!(libc::read(read_fd, buf.as_mut_ptr().cast(), buf.len()), 0);
+        assert_eq!(libc::close(read_fd), 0);
+
+        // Writing fails once the read end is closed.
+        assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+        assert_eq!(libc::close(fds[0]), 0);
+        assert_eq!(libc::write(fds[1], b"x".as_ptr().cast(), 1), -1);
+        assert_eq!(errno(), libc::EPIPE);
+        assert_eq!(libc::close(fds[1]), 0);
+    }
+}
@@ -0,0 +1,14 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        let t1: libc::pthread_t = libc::pthread_self();
+        let t2: libc::pthread_t = libc::pthread_self();
+        assert_ne!(libc::pthread_equal(t1, t2), 0);
+        assert_ne!(libc::pthread_equal(t1, libc::pthread_self()), 0);
+    }
+}
@@ -0,0 +1,32 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+extern "C" {
+    fn at_quick_exit(func: extern "C" fn()) -> libc::c_int;
+    fn quick_exit(status: libc::c_int) -> !;
+}
+
+extern "C" fn atexit_handler() {
+    // Only the handlers registered with `at_quick_exit` run.
+    unreachable!();
+}
+
+extern "C" fn first() {
+    println!("first");
+}
+
+extern "C" fn second() {
+    println!("second");
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::atexit(atexit_handler), 0);
+        assert_eq!(at_quick_exit(first), 0);
+        assert_eq!(at_quick_exit(second), 0);
+        quick_exit(0);
+    }
+}
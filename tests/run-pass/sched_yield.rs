@@ -0,0 +1,13 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    // With only one thread, yielding must return right away instead of spinning forever.
+    for _ in 0..10 {
+        assert_eq!(unsafe { libc::sched_yield() }, 0);
+    }
+    std::thread::yield_now();
+}
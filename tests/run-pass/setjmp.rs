@@ -0,0 +1,32 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+type JmpBuf = [libc::c_long; 32];
+
+extern "C" {
+    fn setjmp(env: *mut JmpBuf) -> libc::c_int;
+    fn longjmp(env: *mut JmpBuf, val: libc::c_int) -> !;
+}
+
+fn main() {
+    let mut env: JmpBuf = [0; 32];
+    let mut calls = 0;
+    let result = unsafe { setjmp(&mut env) };
+    calls += 1;
+    if result == 0 {
+        // Jumping back with 0 makes `setjmp` return 1.
+        unsafe { longjmp(&mut env, 0) };
+    }
+    assert_eq!(result, 1);
+    assert_eq!(calls, 2);
+
+    // Calling `setjmp` repeatedly from the same place keeps working.
+    for i in 1..100 {
+        if unsafe { setjmp(&mut env) } == 0 {
+            unsafe { longjmp(&mut env, i) };
+        }
+    }
+}
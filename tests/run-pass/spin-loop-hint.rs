@@ -0,0 +1,9 @@
+use std::sync::atomic::{self, AtomicUsize, Ordering};
+
+fn main() {
+    let counter = AtomicUsize::new(0);
+    while counter.fetch_add(1, Ordering::Relaxed) < 10 {
+        atomic::spin_loop_hint();
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 11);
+}
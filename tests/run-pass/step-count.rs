@@ -0,0 +1,20 @@
+extern "Rust" {
+    fn miri_step_count() -> u64;
+}
+
+fn sum(n: u64) -> u64 {
+    (0..n).sum()
+}
+
+fn main() {
+    let start = unsafe { miri_step_count() };
+    assert_eq!(sum(10), 45);
+    let short = unsafe { miri_step_count() } - start;
+    assert!(short > 0);
+
+    let start = unsafe { miri_step_count() };
+    assert_eq!(sum(100), 4950);
+    let long = unsafe { miri_step_count() } - start;
+    assert!(long > short);
+    assert!(long < 100_000, "too many steps: {}", long);
+}
@@ -0,0 +1,36 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ffi::CStr;
+use std::io;
+
+fn main() {
+    let error = io::Error::from_raw_os_error(libc::ENOENT);
+    assert_eq!(error.to_string(), "No such file or directory (os error 2)");
+
+    unsafe {
+        let message = CStr::from_ptr(libc::strerror(libc::EINVAL));
+        assert_eq!(message.to_str().unwrap(), "Invalid argument");
+        // Repeated calls return the same buffer.
+        assert_eq!(libc::strerror(libc::EINVAL), libc::strerror(libc::EINVAL));
+        let message = CStr::from_ptr(libc::strerror(libc::ERANGE)).to_str().unwrap();
+        if cfg!(target_os = "macos") {
+            assert_eq!(message, "Result too large");
+        } else {
+            assert_eq!(message, "Numerical result out of range");
+        }
+
+        // The message gets truncated if the buffer is too small.
+        let mut buf = [1 as libc::c_char; 4];
+        assert_eq!(libc::strerror_r(libc::EPERM, buf.as_mut_ptr(), buf.len()), libc::ERANGE);
+        assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str().unwrap(), "Ope");
+
+        // Unknown error numbers get a generic message.
+        let mut buf = [0 as libc::c_char; 64];
+        assert_eq!(libc::strerror_r(12345, buf.as_mut_ptr(), buf.len()), libc::EINVAL);
+        assert!(CStr::from_ptr(buf.as_ptr()).to_str().unwrap().starts_with("Unknown error"));
+    }
+}
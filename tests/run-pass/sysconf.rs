@@ -0,0 +1,18 @@
+// ignore-windows: No libc on Windows
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::sysconf(libc::_SC_PAGESIZE), 4096);
+        assert_eq!(libc::sysconf(libc::_SC_NPROCESSORS_ONLN), 1);
+        assert_eq!(libc::sysconf(libc::_SC_OPEN_MAX), 1024);
+        assert_eq!(libc::sysconf(libc::_SC_CLK_TCK), 100);
+
+        // Unknown names are reported through `errno`.
+        assert_eq!(libc::sysconf(-1), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+    }
+}
@@ -0,0 +1,29 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use std::ptr;
+
+static mut KEYS: [libc::pthread_key_t; 2] = [0; 2];
+static mut CALLS: usize = 0;
+static mut VALUE: u8 = 0;
+
+unsafe extern "C" fn dtor(_ptr: *mut libc::c_void) {
+    CALLS += 1;
+    println!("dtor call {}", CALLS);
+    // Each destructor sets the value of the other key, so there are always outstanding values;
+    // destructors run for at most `PTHREAD_DESTRUCTOR_ITERATIONS` rounds.
+    let other = if CALLS % 2 == 1 { KEYS[1] } else { KEYS[0] };
+    assert_eq!(libc::pthread_setspecific(other, &VALUE as *const u8 as *const _), 0);
+}
+
+fn main() {
+    unsafe {
+        for key in KEYS.iter_mut() {
+            assert_eq!(libc::pthread_key_create(key, Some(dtor)), 0);
+        }
+        assert_eq!(libc::pthread_setspecific(KEYS[0], &VALUE as *const u8 as *const _), 0);
+        assert_eq!(libc::pthread_getspecific(KEYS[1]), ptr::null_mut());
+    }
+}
@@ -0,0 +1,22 @@
+// ignore-windows: No libc on Windows
+// ignore-macos: Uses Linux-only APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::ffi::CStr;
+use std::mem::MaybeUninit;
+
+fn main() {
+    let mut buf = MaybeUninit::<libc::utsname>::uninit();
+    assert_eq!(unsafe { libc::uname(buf.as_mut_ptr()) }, 0);
+    let buf = unsafe { buf.assume_init() };
+
+    let field = |f: &[libc::c_char]| unsafe { CStr::from_ptr(f.as_ptr()) }.to_str().unwrap().to_owned();
+    assert_eq!(field(&buf.sysname), "Linux");
+    assert_eq!(field(&buf.nodename), "miri");
+    assert_eq!(field(&buf.release), "5.15.0");
+    assert_eq!(field(&buf.version), "#1 Miri");
+    assert!(!field(&buf.machine).is_empty());
+}
@@ -0,0 +1,58 @@
+// ignore-linux: Uses Windows-only APIs
+// ignore-macos: Uses Windows-only APIs
+
+use std::ptr;
+use std::os::raw::c_void;
+
+type InitOnce = *mut c_void;
+type Bool = i32;
+
+const INIT_ONCE_STATIC_INIT: InitOnce = ptr::null_mut();
+const INIT_ONCE_INIT_FAILED: u32 = 4;
+
+extern "system" {
+    fn InitOnceExecuteOnce(
+        init_once: *mut InitOnce,
+        init_fn: extern "system" fn(*mut InitOnce, *mut c_void, *mut *mut c_void) -> Bool,
+        parameter: *mut c_void,
+        context: *mut *mut c_void,
+    ) -> Bool;
+    fn InitOnceBeginInitialize(
+        init_once: *mut InitOnce,
+        flags: u32,
+        pending: *mut Bool,
+        context: *mut *mut c_void,
+    ) -> Bool;
+    fn InitOnceComplete(init_once: *mut InitOnce, flags: u32, context: *mut c_void) -> Bool;
+}
+
+/// Increments the counter in `parameter`, and fails on the first call.
+extern "system" fn init(_init_once: *mut InitOnce, parameter: *mut c_void, _context: *mut *mut c_void) -> Bool {
+    let counter = unsafe { &mut *(parameter as *mut u32) };
+    *counter += 1;
+    (*counter > 1) as Bool
+}
+
+fn main() {
+    unsafe {
+        let mut once = INIT_ONCE_STATIC_INIT;
+        let mut counter = 0u32;
+        let parameter = &mut counter as *mut u32 as *mut c_void;
+        // The first attempt fails, so the second one runs `init` again.
+        assert_eq!(InitOnceExecuteOnce(&mut once, init, parameter, ptr::null_mut()), 0);
+        assert_eq!(InitOnceExecuteOnce(&mut once, init, parameter, ptr::null_mut()), 1);
+        assert_eq!(InitOnceExecuteOnce(&mut once, init, parameter, ptr::null_mut()), 1);
+        assert_eq!(counter, 2);
+
+        let mut once = INIT_ONCE_STATIC_INIT;
+        let mut pending = 0;
+        assert_eq!(InitOnceBeginInitialize(&mut once, 0, &mut pending, ptr::null_mut()), 1);
+        assert_eq!(pending, 1);
+        assert_eq!(InitOnceComplete(&mut once, INIT_ONCE_INIT_FAILED, ptr::null_mut()), 1);
+        assert_eq!(InitOnceBeginInitialize(&mut once, 0, &mut pending, ptr::null_mut()), 1);
+        assert_eq!(pending, 1);
+        assert_eq!(InitOnceComplete(&mut once, 0, ptr::null_mut()), 1);
+        assert_eq!(InitOnceBeginInitialize(&mut once, 0, &mut pending, ptr::null_mut()), 1);
+        assert_eq!(pending, 0);
+    }
+}
@@ -0,0 +1,36 @@
+// ignore-linux: Uses Windows-only APIs
+// ignore-macos: Uses Windows-only APIs
+
+extern "system" {
+    fn InterlockedExchange(target: *mut i32, value: i32) -> i32;
+    fn InterlockedExchangeAdd(addend: *mut i32, value: i32) -> i32;
+    fn InterlockedIncrement(addend: *mut i32) -> i32;
+    fn InterlockedDecrement(addend: *mut i32) -> i32;
+    fn InterlockedCompareExchange(destination: *mut i32, exchange: i32, comparand: i32) -> i32;
+    fn InterlockedExchange64(target: *mut i64, value: i64) -> i64;
+    fn InterlockedIncrement64(addend: *mut i64) -> i64;
+    fn InterlockedCompareExchange64(destination: *mut i64, exchange: i64, comparand: i64) -> i64;
+}
+
+fn main() {
+    unsafe {
+        let mut x = 0i32;
+        assert_eq!(InterlockedExchange(&mut x, 5), 0);
+        assert_eq!(InterlockedExchangeAdd(&mut x, 3), 5);
+        // Increment and decrement return the new value.
+        assert_eq!(InterlockedIncrement(&mut x), 9);
+        assert_eq!(InterlockedDecrement(&mut x), 8);
+        assert_eq!(InterlockedCompareExchange(&mut x, 1, 0), 8);
+        assert_eq!(x, 8);
+        assert_eq!(InterlockedCompareExchange(&mut x, 1, 8), 8);
+        assert_eq!(x, 1);
+        x = i32::MAX;
+        assert_eq!(InterlockedIncrement(&mut x), i32::MIN);
+
+        let mut y = 0i64;
+        assert_eq!(InterlockedExchange64(&mut y, 1 << 40), 0);
+        assert_eq!(InterlockedIncrement64(&mut y), (1 << 40) + 1);
+        assert_eq!(InterlockedCompareExchange64(&mut y, 7, (1 << 40) + 1), (1 << 40) + 1);
+        assert_eq!(y, 7);
+    }
+}
@@ -0,0 +1,66 @@
+// ignore-linux: Uses Windows-only APIs
+// ignore-macos: Uses Windows-only APIs
+
+use std::ptr;
+
+type Bool = i32;
+type Handle = isize;
+
+const WAIT_OBJECT_0: u32 = 0;
+const INFINITE: u32 = u32::MAX;
+const SYNCHRONIZE: u32 = 0x00100000;
+const ERROR_FILE_NOT_FOUND: u32 = 2;
+const ERROR_ALREADY_EXISTS: u32 = 183;
+const ERROR_NOT_OWNER: u32 = 288;
+
+extern "system" {
+    fn CreateMutexW(attributes: *mut u8, initial_owner: Bool, name: *const u16) -> Handle;
+    fn OpenMutexW(access: u32, inherit: Bool, name: *const u16) -> Handle;
+    fn WaitForSingleObject(handle: Handle, milliseconds: u32) -> u32;
+    fn ReleaseMutex(handle: Handle) -> Bool;
+    fn CloseHandle(handle: Handle) -> Bool;
+    fn GetLastError() -> u32;
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+fn main() {
+    unsafe {
+        // An anonymous mutex is recursive.
+        let mutex = CreateMutexW(ptr::null_mut(), 0, ptr::null());
+        assert_ne!(mutex, 0);
+        assert_eq!(ReleaseMutex(mutex), 0);
+        assert_eq!(GetLastError(), ERROR_NOT_OWNER);
+        assert_eq!(WaitForSingleObject(mutex, INFINITE), WAIT_OBJECT_0);
+        assert_eq!(WaitForSingleObject(mutex, 0), WAIT_OBJECT_0);
+        assert_eq!(ReleaseMutex(mutex), 1);
+        assert_eq!(ReleaseMutex(mutex), 1);
+        assert_eq!(ReleaseMutex(mutex), 0);
+        assert_eq!(CloseHandle(mutex), 1);
+
+        // Named mutexes can be opened again, and are initially held if requested.
+        let name = wide("miri-test-mutex");
+        assert_eq!(OpenMutexW(SYNCHRONIZE, 0, name.as_ptr()), 0);
+        assert_eq!(GetLastError(), ERROR_FILE_NOT_FOUND);
+        let mutex = CreateMutexW(ptr::null_mut(), 1, name.as_ptr());
+        assert_ne!(mutex, 0);
+        assert_eq!(GetLastError(), 0);
+        let again = CreateMutexW(ptr::null_mut(), 1, name.as_ptr());
+        assert_ne!(again, 0);
+        assert_ne!(again, mutex);
+        assert_eq!(GetLastError(), ERROR_ALREADY_EXISTS);
+        let opened = OpenMutexW(SYNCHRONIZE, 0, name.as_ptr());
+        assert_ne!(opened, 0);
+        // All handles refer to the same mutex, which was only acquired by the first call.
+        assert_eq!(ReleaseMutex(opened), 1);
+        assert_eq!(ReleaseMutex(again), 0);
+        assert_eq!(CloseHandle(mutex), 1);
+        assert_eq!(CloseHandle(again), 1);
+        assert_eq!(CloseHandle(opened), 1);
+
+        // Once all handles are closed, the name is gone.
+        assert_eq!(OpenMutexW(SYNCHRONIZE, 0, name.as_ptr()), 0);
+    }
+}
@@ -0,0 +1,17 @@
+// ignore-linux: Uses Windows-only APIs
+// ignore-macos: Uses Windows-only APIs
+
+type Bool = i32;
+
+extern "system" {
+    fn Sleep(milliseconds: u32);
+    fn SleepEx(milliseconds: u32, alertable: Bool) -> u32;
+}
+
+fn main() {
+    // Zero-length sleeps do not need the host clock, so they work with isolation.
+    unsafe {
+        Sleep(0);
+        assert_eq!(SleepEx(0, 0), 0);
+    }
+}